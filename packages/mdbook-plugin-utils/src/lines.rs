@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+use anyhow::{Result, anyhow};
+
+/// Parse a one-based, inclusive line range (e.g. `1-10`, `5`, `5-` or `-10`) into a zero-based, exclusive range.
+pub fn parse_line_range(range: &str) -> Result<Range<usize>> {
+    let parse = |value: &str, default: usize| -> Result<usize> {
+        match value.trim() {
+            "" => Ok(default),
+            value => value
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid line number `{value}` in line range `{range}`.")),
+        }
+    };
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (parse(start, 1)?, parse(end, usize::MAX)?),
+        None => {
+            let line = parse(range, 1)?;
+            (line, line)
+        }
+    };
+
+    if start == 0 || end < start {
+        return Err(anyhow!("Invalid line range `{range}`."));
+    }
+
+    Ok((start - 1)..end)
+}
+
+/// Take a range of lines from a string.
+pub fn take_lines(content: &str, range: Range<usize>) -> String {
+    content
+        .lines()
+        .skip(range.start)
+        .take(range.end.saturating_sub(range.start))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn anchor_name<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    line.split_once(marker).map(|(_, rest)| {
+        let rest = rest.trim_start();
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        &rest[..end]
+    })
+}
+
+/// Take the lines between `ANCHOR: name` and `ANCHOR_END: name` from a string.
+///
+/// Lines containing other anchors are omitted.
+pub fn take_anchored_lines(content: &str, anchor: &str) -> Option<String> {
+    let mut lines: Vec<&str> = vec![];
+    let mut found = false;
+
+    for line in content.lines() {
+        if found {
+            if let Some(name) = anchor_name(line, "ANCHOR_END:") {
+                if name == anchor {
+                    return Some(lines.join("\n"));
+                }
+            } else if anchor_name(line, "ANCHOR:").is_none() {
+                lines.push(line);
+            }
+        } else if anchor_name(line, "ANCHOR:") == Some(anchor) {
+            found = true;
+        }
+    }
+
+    found.then(|| lines.join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_line_range() -> Result<()> {
+        assert_eq!(0..10, parse_line_range("1-10")?);
+        assert_eq!(4..5, parse_line_range("5")?);
+        assert_eq!(4..usize::MAX, parse_line_range("5-")?);
+        assert_eq!(0..10, parse_line_range("-10")?);
+
+        assert!(parse_line_range("0-10").is_err());
+        assert!(parse_line_range("10-5").is_err());
+        assert!(parse_line_range("a-b").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_lines() {
+        let content = "line 1\nline 2\nline 3\nline 4";
+
+        assert_eq!("line 2\nline 3", take_lines(content, 1..3));
+        assert_eq!("line 3\nline 4", take_lines(content, 2..usize::MAX));
+    }
+
+    #[test]
+    fn test_take_anchored_lines() {
+        let content = "\
+        fn main() {\n\
+        // ANCHOR: example\n\
+        let a = 1;\n\
+        // ANCHOR: nested\n\
+        let b = 2;\n\
+        // ANCHOR_END: nested\n\
+        // ANCHOR_END: example\n\
+        }";
+
+        assert_eq!(
+            Some("let a = 1;\nlet b = 2;".to_string()),
+            take_anchored_lines(content, "example")
+        );
+        assert_eq!(
+            Some("let b = 2;".to_string()),
+            take_anchored_lines(content, "nested")
+        );
+        assert_eq!(None, take_anchored_lines(content, "missing"));
+    }
+}
@@ -3,13 +3,13 @@ use std::{
     io::{self, Read},
 };
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use clap::{Args, Parser, Subcommand};
-use log::warn;
+use log::{error, info, warn};
 use mdbook_driver::MDBook;
-use mdbook_preprocessor::Preprocessor;
 use mdbook_preprocessor::{MDBOOK_VERSION, parse_input};
-use mdbook_tabs::TabsPreprocessor;
+use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
+use mdbook_tabs::{TabsPreprocessor, check, config::PreprocessorConfig};
 use semver::{Version, VersionReq};
 
 #[derive(Parser)]
@@ -22,6 +22,7 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    Check,
     Install,
     Supports(SupportsArgs),
 }
@@ -43,6 +44,7 @@ fn main() -> Result<()> {
 
     match &cli.command {
         Some(subcommand) => match subcommand {
+            Commands::Check => handle_check(),
             Commands::Install => handle_install(),
             Commands::Supports(args) => handle_supports(&preprocessor, args),
         },
@@ -50,6 +52,25 @@ fn main() -> Result<()> {
     }
 }
 
+fn handle_check() -> Result<()> {
+    let book = MDBook::load(env::current_dir()?)?;
+
+    let ctx = PreprocessorContext::new(book.root.clone(), book.config.clone(), "html".to_string());
+
+    let diagnostics = check(&ctx, &book.book);
+    for diagnostic in &diagnostics {
+        error!("{diagnostic}");
+    }
+
+    if !diagnostics.is_empty() {
+        bail!("Found {} problem(s) with tabs.", diagnostics.len());
+    }
+
+    info!("No problems found with tabs.");
+
+    Ok(())
+}
+
 fn handle_install() -> Result<()> {
     let book = MDBook::load(env::current_dir()?)?;
     let directory = book.root.join("theme");
@@ -58,8 +79,18 @@ fn handle_install() -> Result<()> {
         fs::create_dir(&directory)?;
     }
 
-    let css_content = include_str!("../theme/tabs.css");
-    let js_content = include_str!("../theme/tabs.js");
+    let config = book
+        .config
+        .get::<PreprocessorConfig>("preprocessor.tabs")?
+        .unwrap_or_default();
+    let class_prefix = config.class_prefix();
+
+    let css_content =
+        include_str!("../theme/tabs.css").replace(".mdbook-tab", &format!(".{class_prefix}-tab"));
+    let js_content = include_str!("../theme/tabs.js").replace(
+        "const classPrefix = 'mdbook';",
+        &format!("const classPrefix = '{class_prefix}';"),
+    );
 
     fs::write(directory.join("tabs.css"), css_content)?;
     fs::write(directory.join("tabs.js"), js_content)?;
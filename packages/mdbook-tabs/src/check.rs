@@ -0,0 +1,631 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use mdbook_preprocessor::{
+    PreprocessorContext,
+    book::{Book, Chapter},
+};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{
+    Deserialize, Deserializer,
+    de::{self, DeserializeOwned, IgnoredAny, Visitor, value},
+    forward_to_deserialize_any,
+};
+
+use crate::{
+    conditions::can_both_match,
+    config::{TabConfig, TabsConfig},
+    manifest::SourceMap,
+    parser::{
+        data::load_data_tabs,
+        dir::load_dir_tabs,
+        tabs::{is_marker, marker_arguments},
+    },
+};
+
+/// Problem found in the tabs usage of a chapter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Marker {
+    Tabs,
+    EndTabs,
+    Tab,
+    EndTab,
+}
+
+impl Marker {
+    fn from_text(text: &str) -> Option<Self> {
+        if is_marker(text, "{{#tabs") {
+            Some(Marker::Tabs)
+        } else if is_marker(text, "{{#endtabs") {
+            Some(Marker::EndTabs)
+        } else if is_marker(text, "{{#tab") {
+            Some(Marker::Tab)
+        } else if is_marker(text, "{{#endtab") {
+            Some(Marker::EndTab)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Marker::Tabs => "{{#tabs}}",
+            Marker::EndTabs => "{{#endtabs}}",
+            Marker::Tab => "{{#tab}}",
+            Marker::EndTab => "{{#endtab}}",
+        }
+    }
+}
+
+/// Names of the fields of a config, i.e. the arguments it accepts.
+fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldNames<'_> {
+        type Error = value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("expected a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("only the field names are read"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Name of an argument, which is parsed as identifier like the fields of a config.
+#[derive(Eq, Ord, PartialEq, PartialOrd)]
+struct ArgumentName(String);
+
+impl<'de> Deserialize<'de> for ArgumentName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArgumentNameVisitor;
+
+        impl Visitor<'_> for ArgumentNameVisitor {
+            type Value = ArgumentName;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an argument name")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(ArgumentName(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(ArgumentNameVisitor)
+    }
+}
+
+/// Arguments which are not accepted by a config.
+///
+/// The preprocessor ignores unknown arguments, so only this check reports them.
+fn unknown_arguments<T: DeserializeOwned>(arguments: &str) -> Vec<String> {
+    let fields = field_names::<T>();
+
+    serde_keyvalue::from_key_values::<BTreeMap<ArgumentName, IgnoredAny>>(arguments)
+        .map(|arguments| {
+            arguments
+                .into_keys()
+                .map(|ArgumentName(name)| name)
+                .filter(|name| !fields.contains(&name.as_str()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+struct Tab {
+    config: Option<TabConfig>,
+    start: usize,
+    content_start: usize,
+}
+
+struct Group {
+    config: Option<TabsConfig>,
+    start: usize,
+    tabs: Vec<TabConfig>,
+    tab: Option<Tab>,
+}
+
+struct GlobalGroup {
+    path: PathBuf,
+    line: usize,
+    tab_names: Vec<String>,
+}
+
+struct ChapterChecker<'a> {
+    ctx: &'a PreprocessorContext,
+    chapter: &'a Chapter,
+    source_map: SourceMap,
+    path: PathBuf,
+    diagnostics: Vec<Diagnostic>,
+    global_groups: Vec<(String, GlobalGroup)>,
+}
+
+impl<'a> ChapterChecker<'a> {
+    fn new(ctx: &'a PreprocessorContext, chapter: &'a Chapter) -> Self {
+        Self {
+            ctx,
+            chapter,
+            source_map: SourceMap::new(&chapter.content),
+            path: chapter
+                .source_path
+                .as_ref()
+                .map(|path| ctx.config.book.src.join(path))
+                .unwrap_or_else(|| PathBuf::from(&chapter.name)),
+            diagnostics: vec![],
+            global_groups: vec![],
+        }
+    }
+
+    fn report(&mut self, offset: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            path: self.path.clone(),
+            line: self.source_map.line(offset),
+            message,
+        });
+    }
+
+    /// Record the tab names of a tabs group with a global, to compare them with the other groups of the book.
+    fn add_global_group(&mut self, global: String, start: usize, tabs: &[TabConfig]) {
+        self.global_groups.push((
+            global,
+            GlobalGroup {
+                path: self.path.clone(),
+                line: self.source_map.line(start),
+                tab_names: tabs.iter().map(|tab| tab.name.clone()).collect(),
+            },
+        ));
+    }
+
+    /// Load the tabs of a standalone tabs group with a global from its data file or directory.
+    fn check_standalone(&mut self, start: usize, end: usize, mut config: TabsConfig) {
+        let Some(global) = config.global.clone() else {
+            return;
+        };
+
+        let span = start..end;
+        let loaded = load_data_tabs(self.ctx, self.chapter, &self.source_map, &span, &mut config)
+            .and_then(|()| {
+                load_dir_tabs(self.ctx, self.chapter, &self.source_map, &span, &mut config)
+            });
+
+        match loaded {
+            Ok(()) => {
+                let tabs = config
+                    .tabs
+                    .into_iter()
+                    .map(|(tab, _)| tab)
+                    .collect::<Vec<_>>();
+                self.add_global_group(global, start, &tabs);
+            }
+            Err(err) => self.report(start, err.to_string()),
+        }
+    }
+
+    fn close_tab(&mut self, group: &mut Group, end: usize) {
+        let Some(tab) = group.tab.take() else {
+            return;
+        };
+        let Some(config) = tab.config else {
+            return;
+        };
+
+        if self.chapter.content[tab.content_start..end]
+            .trim()
+            .is_empty()
+            && config.include.is_none()
+        {
+            self.report(tab.start, format!("Tab `{}` is empty.", config.name));
+        }
+
+        // Tabs with mutually exclusive conditions are never rendered together, so they can share a name.
+        let is_duplicate = group.tabs.iter().any(|other| {
+            other.name == config.name
+                && match (&other.when, &config.when) {
+                    (Some(a), Some(b)) => can_both_match(a, b).unwrap_or(true),
+                    _ => true,
+                }
+        });
+        if is_duplicate {
+            self.report(
+                tab.start,
+                format!("Duplicate tab `{}` in tabs group.", config.name),
+            );
+        }
+        group.tabs.push(config);
+    }
+
+    fn check(mut self) -> Self {
+        let content = &self.chapter.content;
+
+        let mut groups: Vec<Group> = vec![];
+        let mut code_block_level = 0;
+
+        for (event, span) in Parser::new(content).into_offset_iter() {
+            let text = match event {
+                Event::Start(Tag::CodeBlock(_)) => {
+                    code_block_level += 1;
+                    continue;
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    code_block_level -= 1;
+                    continue;
+                }
+                Event::Text(text) if code_block_level == 0 => text,
+                _ => continue,
+            };
+
+            let Some(marker) = Marker::from_text(&text) else {
+                continue;
+            };
+            let (arguments, marker_end) =
+                marker_arguments(content, span.start, marker.name().trim_end_matches("}}"));
+
+            match marker {
+                Marker::Tabs => {
+                    let config = match TabsConfig::parse(arguments) {
+                        Ok(config) => {
+                            for argument in unknown_arguments::<TabsConfig>(arguments) {
+                                self.report(
+                                    span.start,
+                                    format!("Unknown tabs argument `{argument}`."),
+                                );
+                            }
+
+                            Some(config)
+                        }
+                        Err(err) => {
+                            self.report(span.start, format!("Invalid tabs arguments: {err}"));
+                            None
+                        }
+                    };
+
+                    if let Some(config) = config.as_ref().filter(|config| config.is_standalone()) {
+                        self.check_standalone(span.start, marker_end, config.clone());
+                        continue;
+                    }
+
+                    groups.push(Group {
+                        config,
+                        start: span.start,
+                        tabs: vec![],
+                        tab: None,
+                    });
+                }
+                Marker::Tab => {
+                    let config = match TabConfig::parse(arguments) {
+                        Ok(config) => {
+                            for argument in unknown_arguments::<TabConfig>(arguments) {
+                                self.report(
+                                    span.start,
+                                    format!("Unknown tab argument `{argument}`."),
+                                );
+                            }
+
+                            Some(config)
+                        }
+                        Err(err) => {
+                            self.report(span.start, format!("Invalid tab arguments: {err}"));
+                            None
+                        }
+                    };
+
+                    let Some(mut group) = groups.pop() else {
+                        self.report(span.start, "Tab outside of a tabs group.".to_string());
+                        continue;
+                    };
+
+                    if let Some(Tab {
+                        config: Some(config),
+                        start,
+                        ..
+                    }) = &group.tab
+                    {
+                        self.report(*start, format!("Tab `{}` is not closed.", config.name));
+                    }
+                    self.close_tab(&mut group, span.start);
+
+                    group.tab = Some(Tab {
+                        config,
+                        start: span.start,
+                        content_start: marker_end,
+                    });
+                    groups.push(group);
+                }
+                Marker::EndTab => match groups.last_mut() {
+                    Some(group) if group.tab.is_some() => {
+                        let mut group = groups.pop().expect("Group should exist.");
+                        self.close_tab(&mut group, span.start);
+                        groups.push(group);
+                    }
+                    _ => {
+                        self.report(span.start, "Orphan `{{#endtab}}`.".to_string());
+                    }
+                },
+                Marker::EndTabs => {
+                    let Some(mut group) = groups.pop() else {
+                        self.report(span.start, "Orphan `{{#endtabs}}`.".to_string());
+                        continue;
+                    };
+
+                    if let Some(Tab {
+                        config: Some(config),
+                        start,
+                        ..
+                    }) = &group.tab
+                    {
+                        self.report(*start, format!("Tab `{}` is not closed.", config.name));
+                    }
+                    self.close_tab(&mut group, span.start);
+
+                    if group.tabs.is_empty() {
+                        self.report(group.start, "Tabs group has no tabs.".to_string());
+                    }
+
+                    if let Some(global) = group
+                        .config
+                        .as_ref()
+                        .and_then(|config| config.global.clone())
+                    {
+                        self.add_global_group(global, group.start, &group.tabs);
+                    }
+                }
+            }
+        }
+
+        for group in groups {
+            self.report(group.start, "Tabs group is not closed.".to_string());
+        }
+
+        self
+    }
+}
+
+/// Check the tabs usage of all chapters in a book.
+///
+/// Standalone tabs groups with a global are loaded from their data file or directory, relative to the book root.
+pub fn check(ctx: &PreprocessorContext, book: &Book) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut global_groups: BTreeMap<String, Vec<GlobalGroup>> = BTreeMap::new();
+
+    for chapter in book.chapters() {
+        let checker = ChapterChecker::new(ctx, chapter).check();
+
+        diagnostics.extend(checker.diagnostics);
+        for (global, group) in checker.global_groups {
+            global_groups.entry(global).or_default().push(group);
+        }
+    }
+
+    for (global, groups) in global_groups {
+        let Some((first, others)) = groups.split_first() else {
+            continue;
+        };
+
+        let mut first_names = first.tab_names.clone();
+        first_names.sort();
+
+        for group in others {
+            let mut names = group.tab_names.clone();
+            names.sort();
+
+            if names != first_names {
+                diagnostics.push(Diagnostic {
+                    path: group.path.clone(),
+                    line: group.line,
+                    message: format!(
+                        "Tabs group with global `{global}` has tabs `{}`, but the group at {}:{} has tabs `{}`.",
+                        group.tab_names.join("`, `"),
+                        first.path.display(),
+                        first.line,
+                        first.tab_names.join("`, `"),
+                    ),
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use anyhow::Result;
+    use mdbook_preprocessor::book::BookItem;
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::{chapter, context};
+
+    fn diagnostics(root: &Path, chapters: &[(&str, &str)]) -> Result<Vec<String>> {
+        let book = Book::new_with_items(
+            chapters
+                .iter()
+                .map(|(path, content)| BookItem::Chapter(chapter(path, content)))
+                .collect(),
+        );
+
+        Ok(check(&context(root, "", "html")?, &book)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect())
+    }
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let diagnostics = diagnostics(
+            Path::new(""),
+            &[
+                (
+                    "a.md",
+                    "\
+                    {{#tabs global=\"os\" }}\n\
+                    {{#tab name=\"Linux\" }}\n\
+                    Content.\n\
+                    {{#endtab }}\n\
+                    {{#tab name=\"Linux\" }}\n\
+                    {{#endtab }}\n\
+                    {{#endtabs }}\n\
+                    \n\
+                    {{#endtab }}\n\
+                    \n\
+                    {{#tab name=\"Outside\" }}\n\
+                    \n\
+                    {{#tabs global=\"os\",,}}\n\
+                    {{#tab name=\"Unclosed\" }}\n\
+                    Content.\n\
+                    {{#endtabs }}\n\
+                    \n\
+                    ```markdown\n\
+                    {{#endtab }}\n\
+                    ```\n\
+                    ",
+                ),
+                (
+                    "b.md",
+                    "\
+                    {{#tabs global=\"os\" }}\n\
+                    {{#tab name=\"Windows\",color=\"blue\" }}\n\
+                    Content.\n\
+                    {{#endtab }}\n\
+                    {{#endtabs }}\n\
+                    \n\
+                    {{#tabs data=\"install.toml\" }}\n\
+                    \n\
+                    {{#tabs }}\n\
+                    ",
+                ),
+            ],
+        )?;
+
+        assert_eq!(
+            vec![
+                "src/a.md:5: Tab `Linux` is empty.",
+                "src/a.md:5: Duplicate tab `Linux` in tabs group.",
+                "src/a.md:9: Orphan `{{#endtab}}`.",
+                "src/a.md:11: Tab outside of a tabs group.",
+                "src/a.md:13: Invalid tabs arguments: expected an identifier at position 12",
+                "src/a.md:14: Tab `Unclosed` is not closed.",
+                "src/b.md:2: Unknown tab argument `color`.",
+                "src/b.md:9: Tabs group is not closed.",
+                "src/b.md:1: Tabs group with global `os` has tabs `Windows`, but the group at src/a.md:1 has tabs `Linux`, `Linux`.",
+            ],
+            diagnostics
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_conditional_duplicates() -> Result<()> {
+        assert_eq!(
+            vec!["src/a.md:8: Duplicate tab `Install` in tabs group."],
+            diagnostics(
+                Path::new(""),
+                &[(
+                    "a.md",
+                    "\
+                    {{#tabs }}\n\
+                    {{#tab name=\"Install\",when=\"edition=cloud\" }}\n\
+                    Cloud.\n\
+                    {{#endtab }}\n\
+                    {{#tab name=\"Install\",when=\"edition!=cloud\" }}\n\
+                    Self-hosted.\n\
+                    {{#endtab }}\n\
+                    {{#tab name=\"Install\",when=\"renderer=html\" }}\n\
+                    HTML.\n\
+                    {{#endtab }}\n\
+                    {{#endtabs }}\n\
+                    ",
+                )],
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_standalone_global() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::write(
+            root.path().join("install.toml"),
+            "[[tabs]]\nname = \"Linux\"\n\n[[tabs]]\nname = \"macOS\"\n",
+        )?;
+        fs::create_dir(root.path().join("os"))?;
+        fs::write(root.path().join("os/Linux"), "")?;
+        fs::write(root.path().join("os/Windows"), "")?;
+
+        assert_eq!(
+            vec![
+                "src/b.md:3: Data file `missing.toml` not found (src/b.md:3).",
+                "src/b.md:1: Tabs group with global `os` has tabs `Linux`, `macOS`, but the group at src/a.md:1 has tabs `Linux`, `Windows`.",
+            ],
+            diagnostics(
+                root.path(),
+                &[
+                    (
+                        "a.md",
+                        "\
+                        {{#tabs global=\"os\" }}\n\
+                        {{#tab name=\"Linux\" }}\n\
+                        Linux.\n\
+                        {{#endtab }}\n\
+                        {{#tab name=\"Windows\" }}\n\
+                        Windows.\n\
+                        {{#endtab }}\n\
+                        {{#endtabs }}\n\
+                        \n\
+                        {{#tabs dir=\"os\",global=\"os\" }}\n\
+                        ",
+                    ),
+                    (
+                        "b.md",
+                        "\
+                        {{#tabs data=\"install.toml\",global=\"os\" }}\n\
+                        \n\
+                        {{#tabs data=\"missing.toml\",global=\"os\" }}\n\
+                        ",
+                    ),
+                ],
+            )?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+};
+
+use anyhow::{Result, bail};
+
+/// Variables which tab conditions are evaluated against.
+#[derive(Clone, Debug, Default)]
+pub struct Vars {
+    values: BTreeMap<String, String>,
+}
+
+impl Vars {
+    pub fn new(values: BTreeMap<String, String>) -> Self {
+        Self { values }
+    }
+
+    /// Value of a variable, falling back to the `MDBOOK_TABS_<NAME>` environment variable.
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.values.get(name).cloned().or_else(|| {
+            env::var(format!(
+                "MDBOOK_TABS_{}",
+                name.to_uppercase().replace('-', "_")
+            ))
+            .ok()
+        })
+    }
+}
+
+/// Part of a condition, e.g. `edition=cloud` or `renderer!=html|epub`.
+struct Clause<'a> {
+    name: &'a str,
+    values: BTreeSet<&'a str>,
+    negated: bool,
+}
+
+/// Parse the comma-separated parts of a condition.
+fn parse(condition: &str) -> Result<Vec<Clause<'_>>> {
+    condition
+        .split(',')
+        .map(str::trim)
+        .map(|part| {
+            let (name, values, negated) = match part.split_once("!=") {
+                Some((name, values)) => (name, values, true),
+                None => match part.split_once('=') {
+                    Some((name, values)) => (name, values, false),
+                    None => {
+                        bail!("Invalid condition `{part}`, expected `name=value` or `name!=value`.")
+                    }
+                },
+            };
+
+            Ok(Clause {
+                name: name.trim(),
+                values: values.split('|').map(str::trim).collect(),
+                negated,
+            })
+        })
+        .collect()
+}
+
+/// Evaluate a condition, e.g. `edition=cloud`, `edition!=cloud` or `renderer=html|epub`.
+///
+/// Multiple comma-separated conditions must all match.
+pub fn evaluate(condition: &str, vars: &Vars) -> Result<bool> {
+    for clause in parse(condition)? {
+        let value = vars.get(clause.name);
+        let matches = value
+            .as_deref()
+            .is_some_and(|value| clause.values.contains(value));
+
+        if matches == clause.negated {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Whether two conditions can both match, i.e. they are not mutually exclusive.
+pub fn can_both_match(a: &str, b: &str) -> Result<bool> {
+    let mut clauses = parse(a)?;
+    clauses.extend(parse(b)?);
+
+    for name in clauses
+        .iter()
+        .map(|clause| clause.name)
+        .collect::<BTreeSet<_>>()
+    {
+        // Values of the variable which match all positive clauses, if any.
+        let mut allowed: Option<BTreeSet<&str>> = None;
+        let mut excluded = BTreeSet::new();
+
+        for clause in clauses.iter().filter(|clause| clause.name == name) {
+            if clause.negated {
+                excluded.extend(clause.values.iter().copied());
+            } else {
+                allowed = Some(match allowed {
+                    Some(allowed) => allowed.intersection(&clause.values).copied().collect(),
+                    None => clause.values.clone(),
+                });
+            }
+        }
+
+        if allowed.is_some_and(|allowed| allowed.is_subset(&excluded)) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate() -> Result<()> {
+        let vars = Vars::new(BTreeMap::from([
+            ("edition".to_string(), "cloud".to_string()),
+            ("renderer".to_string(), "html".to_string()),
+        ]));
+
+        assert!(evaluate("edition=cloud", &vars)?);
+        assert!(!evaluate("edition=self-hosted", &vars)?);
+        assert!(evaluate("edition!=self-hosted", &vars)?);
+        assert!(evaluate("edition=self-hosted|cloud", &vars)?);
+        assert!(evaluate("edition=cloud, renderer=html", &vars)?);
+        assert!(!evaluate("edition=cloud,renderer!=html", &vars)?);
+        assert!(!evaluate("mdbook-tabs-undefined=value", &vars)?);
+        assert!(evaluate("mdbook-tabs-undefined!=value", &vars)?);
+        assert!(evaluate("edition", &vars).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_can_both_match() -> Result<()> {
+        assert!(!can_both_match("edition=cloud", "edition!=cloud")?);
+        assert!(!can_both_match("edition=cloud", "edition=self-hosted")?);
+        assert!(!can_both_match(
+            "edition=cloud|self-hosted",
+            "edition!=cloud|self-hosted"
+        )?);
+        assert!(!can_both_match(
+            "edition=cloud, renderer=html",
+            "renderer=epub"
+        )?);
+        assert!(can_both_match(
+            "edition=cloud",
+            "edition=cloud|self-hosted"
+        )?);
+        assert!(can_both_match("edition=cloud", "renderer=html")?);
+        assert!(can_both_match("edition!=cloud", "edition!=self-hosted")?);
+        assert!(can_both_match("edition", "edition=cloud").is_err());
+
+        Ok(())
+    }
+}
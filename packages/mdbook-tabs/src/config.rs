@@ -1,10 +1,147 @@
+use std::{collections::BTreeMap, ops::Range, path::PathBuf};
+
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeOwned, Error, IntoDeserializer},
+};
+
+use crate::headings::id_from_content;
+
+/// Configuration of the preprocessor in `[preprocessor.tabs]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct PreprocessorConfig {
+    pub fallback: FallbackConfig,
+    pub prefix_heading_ids: bool,
+    pub code_group_global: Option<String>,
+    pub syntaxes: Vec<Syntax>,
+    pub mode: Mode,
+    pub style: Style,
+    pub dropdown_width: Option<u32>,
+    pub storage: Storage,
+    pub storage_namespace: Option<String>,
+    pub remember_per_page: bool,
+    pub vars: BTreeMap<String, String>,
+    pub manifest: Option<PathBuf>,
+    pub class_prefix: Option<String>,
+    pub templates: TemplatesConfig,
+}
+
+impl PreprocessorConfig {
+    /// Prefix of the classes of tabs, which defaults to `mdbook`.
+    pub fn class_prefix(&self) -> &str {
+        self.class_prefix.as_deref().unwrap_or("mdbook")
+    }
+}
+
+/// How tabs switch between tabs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Mode {
+    #[default]
+    Js,
+    Css,
+}
+
+/// Appearance of tabs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Style {
+    #[default]
+    Horizontal,
+    Vertical,
+    Pills,
+}
+
+/// Where selected tabs are remembered.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Storage {
+    #[default]
+    Local,
+    Session,
+    Url,
+    None,
+}
+
+/// Tabs syntax of another documentation tool.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Syntax {
+    Mkdocs,
+    Docusaurus,
+}
 
+/// Configuration of tabs for renderers other than `html`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct FallbackConfig {
+    pub format: FallbackFormat,
+    pub heading_level: u8,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            format: FallbackFormat::default(),
+            heading_level: 4,
+        }
+    }
+}
+
+/// Handlebars templates of the tabs markup, relative to the book root.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TemplatesConfig {
+    pub container: Option<PathBuf>,
+    pub button: Option<PathBuf>,
+    pub panel: Option<PathBuf>,
+}
+
+/// Format of tabs for renderers other than `html`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum FallbackFormat {
+    #[default]
+    Headings,
+    DefinitionList,
+}
+
+/// Arguments of a tabs group and its tabs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct TabsConfig {
     pub global: Option<String>,
+    pub data: Option<String>,
+    pub dir: Option<String>,
+    pub include: Option<String>,
+    pub show_all: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_option_from_str")]
+    pub mode: Option<Mode>,
+    #[serde(default, deserialize_with = "deserialize_option_from_str")]
+    pub style: Option<Style>,
+    pub dropdown_width: Option<u32>,
 
+    /// ID of the tabs container, which is required for rendering.
+    ///
+    /// The preprocessor assigns IDs which are unique within the book, so it's empty for parsed tabs.
+    #[serde(skip)]
+    pub id: String,
+
+    /// Whether conditions removed all tabs but one, so the tabs are replaced by their content.
+    #[serde(skip)]
+    pub(crate) collapse: bool,
+
+    /// Tabs with their Markdown content.
     #[serde(skip)]
     pub tabs: Vec<(TabConfig, String)>,
 }
@@ -13,15 +150,76 @@ impl TabsConfig {
     pub fn parse(content: &str) -> Result<Self, serde_keyvalue::ParseError> {
         serde_keyvalue::from_key_values(content)
     }
+
+    /// Whether the tabs are generated from a data file or directory, so they have no tabs or end marker.
+    pub fn is_standalone(&self) -> bool {
+        self.data.is_some() || self.dir.is_some()
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Arguments of a tab.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct TabConfig {
     pub name: String,
+    pub icon: Option<String>,
+    pub include: Option<String>,
+    pub lang: Option<String>,
+    pub lines: Option<String>,
+    pub anchor: Option<String>,
+    pub when: Option<String>,
+    pub disabled: Option<bool>,
+    pub badge: Option<String>,
+
+    /// Label of the tab as inline Markdown, if it differs from its name, e.g. escaped file names.
+    #[serde(skip)]
+    pub label: Option<String>,
+
+    /// Whether the tab is initially active, e.g. from the `default` attribute of a Docusaurus tab.
+    #[serde(skip)]
+    pub(crate) default: bool,
+
+    /// Span of the tab in the parsed content.
+    #[serde(skip)]
+    pub(crate) span: Range<usize>,
 }
 
 impl TabConfig {
     pub fn parse(content: &str) -> Result<Self, serde_keyvalue::ParseError> {
         serde_keyvalue::from_key_values(content)
     }
+
+    /// Whether the tab is rendered, but can't be selected.
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.unwrap_or(false)
+    }
+
+    /// Whether the tab is initially active instead of the first tab.
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
+    /// Label of the tab as inline Markdown, which defaults to its name.
+    pub fn markdown_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Key of the tab, used as prefix of heading IDs.
+    pub fn key(&self) -> String {
+        id_from_content(&self.name)
+    }
+}
+
+/// Deserialize a value from a string, so enums can be quoted in tag arguments.
+fn deserialize_option_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| {
+            T::deserialize(value.as_str().into_deserializer())
+                .map_err(|err: serde::de::value::Error| D::Error::custom(err))
+        })
+        .transpose()
 }
@@ -0,0 +1,254 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+use mdbook_plugin_utils::lines::{parse_line_range, take_anchored_lines, take_lines};
+use mdbook_preprocessor::{PreprocessorContext, book::Chapter};
+
+use crate::{config::TabConfig, manifest::SourceMap};
+
+/// Location of an offset in the processed content of a chapter, formatted as `path:line`.
+///
+/// The line is in the chapter source, so it is unaffected by tabs which were already replaced.
+pub fn location(
+    ctx: &PreprocessorContext,
+    chapter: &Chapter,
+    source_map: &SourceMap,
+    offset: usize,
+) -> String {
+    let path = chapter
+        .source_path
+        .as_ref()
+        .map(|path| ctx.config.book.src.join(path))
+        .unwrap_or_else(|| PathBuf::from(&chapter.name));
+    let line = source_map.line(source_map.source_offset(offset));
+
+    format!("{}:{}", path.display(), line)
+}
+
+/// Resolve a path relative to the chapter, falling back to the book root.
+pub fn resolve_path(ctx: &PreprocessorContext, chapter: &Chapter, path: &str) -> Option<PathBuf> {
+    let chapter_dir = chapter
+        .source_path
+        .as_ref()
+        .and_then(|source_path| source_path.parent())
+        .map(|parent| ctx.root.join(&ctx.config.book.src).join(parent));
+
+    chapter_dir
+        .map(|directory| directory.join(path))
+        .into_iter()
+        .chain([ctx.root.join(path)])
+        .find(|path| path.exists())
+}
+
+/// Language of a fenced code block for a file, based on its extension.
+pub fn language(path: &Path) -> &str {
+    path.extension().and_then(|s| s.to_str()).unwrap_or("")
+}
+
+/// Fence of a code block, which is longer than any sequence of backticks in its content.
+fn fence(content: &str) -> String {
+    let backticks = content
+        .split(|character| character != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+
+    "`".repeat((backticks + 1).max(3))
+}
+
+/// Tab content with the file included by the tab appended as fenced code block.
+pub fn include(
+    ctx: &PreprocessorContext,
+    chapter: &Chapter,
+    source_map: &SourceMap,
+    tab: &TabConfig,
+    tab_content: &str,
+) -> Result<String> {
+    let Some(include) = &tab.include else {
+        return Ok(tab_content.to_string());
+    };
+
+    let file_path = resolve_path(ctx, chapter, include).ok_or_else(|| {
+        anyhow!(
+            "File `{include}` included by tab `{}` not found ({}).",
+            tab.name,
+            location(ctx, chapter, source_map, tab.span.start)
+        )
+    })?;
+    debug!("Including file `{}`.", file_path.display());
+
+    let mut content = fs::read_to_string(&file_path).with_context(|| {
+        format!(
+            "File `{include}` included by tab `{}` could not be read ({}).",
+            tab.name,
+            location(ctx, chapter, source_map, tab.span.start)
+        )
+    })?;
+
+    if let Some(anchor) = &tab.anchor {
+        content = take_anchored_lines(&content, anchor).ok_or_else(|| {
+            anyhow!(
+                "Anchor `{anchor}` not found in file `{include}` ({}).",
+                location(ctx, chapter, source_map, tab.span.start)
+            )
+        })?;
+    } else if let Some(lines) = &tab.lines {
+        content = take_lines(&content, parse_line_range(lines)?);
+    }
+
+    let content = content.trim_end();
+    let fence = fence(content);
+
+    Ok(format!(
+        "{}\n\n{fence}{}\n{content}\n{fence}\n",
+        tab_content.trim_end(),
+        tab.lang.as_deref().unwrap_or_else(|| language(&file_path)),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::{chapter, context};
+
+    fn tab(include: &str) -> TabConfig {
+        TabConfig::parse(&format!("name=\"Tab\",include=\"{include}\""))
+            .expect("Tab should be valid.")
+    }
+
+    #[test]
+    fn test_include() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir_all(root.path().join("src/guide"))?;
+        fs::create_dir(root.path().join("examples"))?;
+        fs::write(
+            root.path().join("src/guide/main.rs"),
+            "\
+            // ANCHOR: all\n\
+            fn main() {\n\
+            \x20   // ANCHOR: body\n\
+            \x20   println!(\"Hello\");\n\
+            \x20   // ANCHOR_END: body\n\
+            }\n\
+            // ANCHOR_END: all\n\
+            ",
+        )?;
+        fs::write(
+            root.path().join("examples/Cargo.toml"),
+            "[package]\nname = \"hello\"\nedition = \"2024\"\n",
+        )?;
+
+        let ctx = context(root.path(), "", "html")?;
+        let chapter = chapter("guide/index.md", "");
+        let source_map = SourceMap::new("");
+
+        // Paths are relative to the chapter, falling back to the book root.
+        fs::write(root.path().join("main.rs"), "")?;
+        assert_eq!(
+            Some(root.path().join("src/guide/main.rs")),
+            resolve_path(&ctx, &chapter, "main.rs")
+        );
+        assert_eq!(
+            Some(root.path().join("examples/Cargo.toml")),
+            resolve_path(&ctx, &chapter, "examples/Cargo.toml")
+        );
+        assert_eq!(None, resolve_path(&ctx, &chapter, "missing.rs"));
+
+        assert_eq!(
+            "Content.\n\n```rs\nfn main() {\n    println!(\"Hello\");\n}\n```\n",
+            include(
+                &ctx,
+                &chapter,
+                &source_map,
+                &TabConfig {
+                    anchor: Some("all".to_string()),
+                    ..tab("main.rs")
+                },
+                "Content.\n"
+            )?
+        );
+        assert_eq!(
+            "\n\n```rs\n    println!(\"Hello\");\n```\n",
+            include(
+                &ctx,
+                &chapter,
+                &source_map,
+                &TabConfig {
+                    anchor: Some("body".to_string()),
+                    ..tab("main.rs")
+                },
+                ""
+            )?
+        );
+
+        assert_eq!(
+            "\n\n```toml\nname = \"hello\"\nedition = \"2024\"\n```\n",
+            include(
+                &ctx,
+                &chapter,
+                &source_map,
+                &TabConfig {
+                    lines: Some("2-3".to_string()),
+                    ..tab("examples/Cargo.toml")
+                },
+                ""
+            )?
+        );
+        assert_eq!(
+            "\n\n```ini\n[package]\n```\n",
+            include(
+                &ctx,
+                &chapter,
+                &source_map,
+                &TabConfig {
+                    lang: Some("ini".to_string()),
+                    lines: Some("1".to_string()),
+                    ..tab("examples/Cargo.toml")
+                },
+                ""
+            )?
+        );
+
+        // Fences in the file don't close the code block.
+        fs::write(
+            root.path().join("src/guide/snippet.md"),
+            "# Snippet\n\n```rust\nfn main() {}\n```\n",
+        )?;
+        assert_eq!(
+            "\n\n````md\n# Snippet\n\n```rust\nfn main() {}\n```\n````\n",
+            include(&ctx, &chapter, &source_map, &tab("snippet.md"), "")?
+        );
+
+        fs::write(root.path().join("src/guide/binary.bin"), [0xff, 0xfe])?;
+        assert_eq!(
+            "File `binary.bin` included by tab `Tab` could not be read (src/guide/index.md:1).",
+            include(&ctx, &chapter, &source_map, &tab("binary.bin"), "")
+                .unwrap_err()
+                .to_string()
+        );
+
+        assert_eq!(
+            "Anchor `missing` not found in file `main.rs` (src/guide/index.md:1).",
+            include(
+                &ctx,
+                &chapter,
+                &source_map,
+                &TabConfig {
+                    anchor: Some("missing".to_string()),
+                    ..tab("main.rs")
+                },
+                ""
+            )
+            .unwrap_err()
+            .to_string()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::{
+    config::TabConfig,
+    parser::tabs::{is_tab_end, is_tab_start, is_tabs_end, is_tabs_start, marker_arguments},
+};
+
+/// Generate an HTML ID from text, using the same algorithm as mdBook.
+pub fn id_from_content(content: &str) -> String {
+    content
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| {
+            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                Some(ch)
+            } else if ch.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Add an explicit ID with the given prefix to all headings without attributes.
+///
+/// Headings inside nested tabs are also prefixed with the keys of their nested tabs, so the same nested tab and heading
+/// in different parent tabs have different IDs.
+pub fn prefix_heading_ids(content: &str, prefix: &str) -> String {
+    let mut replacements: Vec<(Range<usize>, String)> = vec![];
+
+    let mut nested_level = 0;
+    // Prefixes of the nested tabs the events are in.
+    let mut prefixes: Vec<String> = vec![];
+    let mut heading: Option<(Range<usize>, String)> = None;
+
+    for (event, span) in
+        Parser::new_ext(content, Options::ENABLE_HEADING_ATTRIBUTES).into_offset_iter()
+    {
+        if is_tabs_start(&event) {
+            nested_level += 1;
+            continue;
+        }
+        if is_tabs_end(&event) {
+            nested_level -= 1;
+            continue;
+        }
+        if nested_level > 0 && is_tab_start(&event) {
+            let (arguments, _) = marker_arguments(content, span.start, "{{#tab");
+            let key = TabConfig::parse(arguments).map_or_else(|_| String::new(), |tab| tab.key());
+            prefixes.push(format!(
+                "{}-{key}",
+                prefixes.last().map_or(prefix, String::as_str)
+            ));
+            continue;
+        }
+        if nested_level > 0 && is_tab_end(&event) {
+            prefixes.pop();
+            continue;
+        }
+
+        match event {
+            Event::Start(Tag::Heading {
+                id: None,
+                classes,
+                attrs,
+                ..
+            }) if classes.is_empty() && attrs.is_empty() => {
+                let line_end = content[span.start..]
+                    .find('\n')
+                    .map(|index| span.start + index)
+                    .unwrap_or(span.end);
+                let line = content[span.start..line_end].trim_end();
+
+                // Remove the closing sequence of ATX headings, as the ID has to be at the end.
+                let line_end = span.start + line.len();
+                let mut end = line_end;
+                if line.trim_start().starts_with('#') {
+                    let without_closing = line.trim_end_matches('#');
+                    if without_closing.ends_with(char::is_whitespace) {
+                        end = span.start + without_closing.trim_end().len();
+                    }
+                }
+
+                heading = Some((end..line_end, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((range, heading_text)) = heading.take() {
+                    replacements.push((
+                        range,
+                        format!(
+                            " {{#{}-{}}}",
+                            prefixes.last().map_or(prefix, String::as_str),
+                            id_from_content(&heading_text)
+                        ),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut content = content.to_string();
+    for (range, replacement) in replacements.into_iter().rev() {
+        content.replace_range(range, &replacement);
+    }
+    content
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_prefix_heading_ids() {
+        let content = "\
+        ## Installation\n\
+        \n\
+        ### Closed ###\n\
+        \n\
+        ## C#\n\
+        \n\
+        Setext `code`\n\
+        ---\n\
+        \n\
+        ## Explicit {#explicit}\n\
+        \n\
+        {{#tabs }}\n\
+        {{#tab name=\"CLI\" }}\n\
+        ## Steps\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Desktop app\" }}\n\
+        ## Steps\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+        let expected = "\
+        ## Installation {#linux-installation}\n\
+        \n\
+        ### Closed {#linux-closed}\n\
+        \n\
+        ## C# {#linux-c}\n\
+        \n\
+        Setext `code` {#linux-setext-code}\n\
+        ---\n\
+        \n\
+        ## Explicit {#explicit}\n\
+        \n\
+        {{#tabs }}\n\
+        {{#tab name=\"CLI\" }}\n\
+        ## Steps {#linux-cli-steps}\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Desktop app\" }}\n\
+        ## Steps {#linux-desktop-app-steps}\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        assert_eq!(expected, prefix_heading_ids(content, "linux"));
+    }
+}
@@ -1,6 +1,15 @@
-mod config;
-mod parser;
+mod check;
+pub mod conditions;
+pub mod config;
+mod files;
+mod headings;
+mod manifest;
+pub mod parser;
 mod preprocessor;
-mod tabs;
+pub mod tabs;
+#[cfg(test)]
+mod test_utils;
 
+pub use check::{Diagnostic, check};
 pub use preprocessor::TabsPreprocessor;
+pub use tabs::{HtmlTabsRenderer, TabsRenderer};
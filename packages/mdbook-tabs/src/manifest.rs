@@ -0,0 +1,122 @@
+use std::{ops::Range, path::PathBuf};
+
+use serde::Serialize;
+
+/// Tabs groups of a book, written as JSON manifest.
+#[derive(Debug, Default, Serialize)]
+pub struct Manifest {
+    pub renderer: String,
+    pub groups: Vec<ManifestGroup>,
+}
+
+/// Tabs group in the manifest.
+#[derive(Debug, Serialize)]
+pub struct ManifestGroup {
+    pub chapter: Option<PathBuf>,
+    pub id: String,
+    pub global: Option<String>,
+    pub span: Range<usize>,
+    pub line: usize,
+    pub tabs: Vec<ManifestTab>,
+}
+
+/// Tab of a tabs group in the manifest.
+#[derive(Debug, Serialize)]
+pub struct ManifestTab {
+    pub key: String,
+    pub label: String,
+    pub span: Range<usize>,
+    pub line: usize,
+}
+
+#[derive(Debug)]
+struct Edit {
+    start: usize,
+    old_len: usize,
+    new_len: usize,
+}
+
+/// Maps offsets in processed chapter content back to the chapter source.
+#[derive(Debug)]
+pub struct SourceMap {
+    source: String,
+    edits: Vec<Edit>,
+}
+
+impl SourceMap {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            edits: vec![],
+        }
+    }
+
+    /// Record the replacement of a range of the processed content.
+    pub fn edit(&mut self, range: Range<usize>, new_len: usize) {
+        self.edits.push(Edit {
+            start: range.start,
+            old_len: range.len(),
+            new_len,
+        });
+    }
+
+    /// Offset in the source of an offset in the processed content.
+    ///
+    /// Offsets in replaced content map to the start of the replaced range, e.g. for nested tabs.
+    pub fn source_offset(&self, offset: usize) -> usize {
+        self.edits.iter().rev().fold(offset, |offset, edit| {
+            if offset >= edit.start + edit.new_len {
+                offset - edit.new_len + edit.old_len
+            } else if offset > edit.start {
+                edit.start
+            } else {
+                offset
+            }
+        })
+    }
+
+    /// Range in the source of a range in the processed content.
+    pub fn source_range(&self, range: &Range<usize>) -> Range<usize> {
+        let start = self.source_offset(range.start);
+        start..self.source_offset(range.end).max(start)
+    }
+
+    /// Line number of an offset in the source.
+    pub fn line(&self, source_offset: usize) -> usize {
+        self.source[..source_offset.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        let source = "a\n{{#tabs}}\nb\n{{#endtabs}}\nc\n{{#tabs}}\nd\n{{#endtabs}}\n";
+        let mut content = source.to_string();
+        let mut source_map = SourceMap::new(source);
+
+        let first = 2..26;
+        let second = 29..53;
+        assert_eq!("{{#tabs}}\nd\n{{#endtabs}}", &source[second.clone()]);
+
+        content.replace_range(first.clone(), "<div>\nb\n</div>");
+        source_map.edit(first.clone(), 14);
+        content.replace_range(0..1, "");
+        source_map.edit(0..1, 0);
+
+        let processed = (second.start - 11)..(second.end - 11);
+        assert_eq!("{{#tabs}}\nd\n{{#endtabs}}", &content[processed.clone()]);
+        assert_eq!(second, source_map.source_range(&processed));
+        assert_eq!(6, source_map.line(second.start));
+
+        // Offsets in replaced content map to the start of the replaced range.
+        assert_eq!(2..2, source_map.source_range(&(7..8)));
+    }
+}
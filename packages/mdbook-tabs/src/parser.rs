@@ -1 +1,26 @@
+pub mod code_group;
+pub(crate) mod data;
+pub(crate) mod dir;
+pub mod docusaurus;
+pub mod mkdocs;
 pub mod tabs;
+
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+
+use crate::config::TabsConfig;
+
+/// Span of a tabs group in the content and its configuration.
+pub type SpanAndTabs = (Range<usize>, TabsConfig);
+
+/// Spans of the fenced code blocks in Markdown content.
+fn fenced_code_block_spans(content: &str) -> Vec<Range<usize>> {
+    Parser::new(content)
+        .into_offset_iter()
+        .filter_map(|(event, span)| match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(_))) => Some(span),
+            _ => None,
+        })
+        .collect()
+}
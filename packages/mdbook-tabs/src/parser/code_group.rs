@@ -0,0 +1,321 @@
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+use log::debug;
+use mdbook_plugin_utils::markdown::parse_blocks;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+
+use super::SpanAndTabs;
+use crate::config::{TabConfig, TabsConfig};
+
+fn is_code_group_start(event: &Event) -> bool {
+    match event {
+        Event::Text(text) => {
+            (text.to_string() == "{{#codegroup}}" || text.starts_with("{{#codegroup "))
+                && !text.contains("{{#endcodegroup")
+        }
+        _ => false,
+    }
+}
+
+fn is_code_group_end(event: &Event) -> bool {
+    match event {
+        Event::Text(text) => {
+            text.to_string() == "{{#endcodegroup}}" || text.starts_with("{{#endcodegroup ")
+        }
+        _ => false,
+    }
+}
+
+/// Split the `tab` attribute from a code block info string.
+///
+/// Returns the tab label and the info string without the attribute.
+fn split_tab_attribute(info: &str) -> Option<(String, String)> {
+    let start = info
+        .match_indices("tab=")
+        .map(|(index, _)| index)
+        .find(|index| *index == 0 || info[..*index].ends_with([',', ' ']))?;
+    let value = &info[start + "tab=".len()..];
+
+    let (label, length) = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted.find('"')?;
+            (quoted[..end].to_string(), end + 2)
+        }
+        None => {
+            let end = value.find([',', ' ']).unwrap_or(value.len());
+            (value[..end].to_string(), end)
+        }
+    };
+
+    let before = info[..start].trim_end_matches([',', ' ']);
+    let after = info[start + "tab=".len() + length..].trim_start_matches([',', ' ']);
+    let info = match (before.is_empty(), after.is_empty()) {
+        (false, false) => format!("{before},{after}"),
+        _ => format!("{before}{after}"),
+    };
+
+    Some((label, info))
+}
+
+/// A fenced code block with its label and source without the `tab` attribute.
+struct CodeBlock {
+    span: Range<usize>,
+    has_tab_attribute: bool,
+    label: Option<String>,
+    content: String,
+}
+
+fn parse_code_block(content: &str, span: Range<usize>, info: &str) -> CodeBlock {
+    let source = &content[span.clone()];
+    let (label, info_without_tab) = match split_tab_attribute(info) {
+        Some((label, info)) => (Some(label), info),
+        None => (None, info.to_string()),
+    };
+    let has_tab_attribute = label.is_some();
+
+    let first_line_end = source.find('\n').unwrap_or(source.len());
+    let first_line = &source[..first_line_end];
+    let fence_end = first_line
+        .find(|c: char| !(c == '`' || c == '~' || c.is_whitespace()))
+        .unwrap_or(first_line.len());
+
+    CodeBlock {
+        span,
+        has_tab_attribute,
+        label: label.or_else(|| {
+            info_without_tab
+                .split(',')
+                .next()
+                .map(|language| language.trim().to_string())
+                .filter(|language| !language.is_empty())
+        }),
+        content: format!(
+            "{}{}{}",
+            &first_line[..fence_end],
+            info_without_tab,
+            &source[first_line_end..]
+        ),
+    }
+}
+
+/// Parse top-level fenced code blocks.
+fn parse_code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut code_blocks = vec![];
+    let mut level = 0;
+
+    for (event, span) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if level == 0 => {
+                code_blocks.push(parse_code_block(content, span, &info));
+                level += 1;
+            }
+            Event::Start(_) => level += 1,
+            Event::End(_) => level -= 1,
+            _ => {}
+        }
+    }
+
+    code_blocks
+}
+
+fn tabs_config(tabs: &mut TabsConfig, code_blocks: Vec<CodeBlock>, offset: usize) {
+    for (index, code_block) in code_blocks.into_iter().enumerate() {
+        tabs.tabs.push((
+            TabConfig {
+                name: code_block
+                    .label
+                    .unwrap_or_else(|| format!("Tab {}", index + 1)),
+                span: (offset + code_block.span.start)..(offset + code_block.span.end),
+                ..Default::default()
+            },
+            code_block.content,
+        ));
+    }
+}
+
+/// Parse code groups, which are either code blocks wrapped in `{{#codegroup}}` or runs of adjacent code blocks with a
+/// `tab` attribute.
+pub fn parse_code_groups(content: &str, global: Option<&String>) -> Result<Vec<SpanAndTabs>> {
+    let mut configs: Vec<SpanAndTabs> = vec![];
+
+    let blocks = parse_blocks(content, is_code_group_start, is_code_group_end, false)?;
+    debug!("{blocks:?}");
+
+    for block in blocks.iter().filter(|block| block.closed) {
+        let start_text = match &block.events[0].0 {
+            Event::Text(text) => text.to_string(),
+            _ => bail!("First event should be text."),
+        };
+
+        let mut tabs = TabsConfig::parse(
+            start_text
+                .trim_start_matches("{{#codegroup")
+                .trim_start()
+                .trim_end_matches("}}")
+                .trim_end(),
+        )?;
+
+        tabs_config(
+            &mut tabs,
+            parse_code_blocks(&content[block.inner_span.clone()]),
+            block.inner_span.start,
+        );
+
+        configs.push((block.span.clone(), tabs));
+    }
+
+    let mut run: Vec<CodeBlock> = vec![];
+    let code_blocks = parse_code_blocks(content).into_iter().filter(|code_block| {
+        code_block.has_tab_attribute
+            && !blocks.iter().any(|block| {
+                block.span.start <= code_block.span.start && code_block.span.end <= block.span.end
+            })
+    });
+
+    let mut push_run = |run: &mut Vec<CodeBlock>| {
+        // A single code block with a `tab` attribute is not a code group.
+        if run.len() < 2 {
+            run.clear();
+            return;
+        }
+
+        if let (Some(first), Some(last)) = (run.first(), run.last()) {
+            let mut tabs = TabsConfig {
+                global: global.cloned(),
+                ..Default::default()
+            };
+            let span = first.span.start..last.span.end;
+
+            tabs_config(&mut tabs, std::mem::take(run), 0);
+            configs.push((span, tabs));
+        }
+    };
+
+    for code_block in code_blocks {
+        if let Some(last) = run.last()
+            && !content[last.span.end..code_block.span.start]
+                .trim()
+                .is_empty()
+        {
+            push_run(&mut run);
+        }
+
+        run.push(code_block);
+    }
+    push_run(&mut run);
+
+    configs.sort_by_key(|(span, _)| span.start);
+    debug!("{configs:?}");
+
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_split_tab_attribute() {
+        assert_eq!(
+            Some(("Rust".to_string(), "rust".to_string())),
+            split_tab_attribute("rust,tab=\"Rust\"")
+        );
+        assert_eq!(
+            Some(("Rust, 2024".to_string(), "rust,edition2024".to_string())),
+            split_tab_attribute("rust,tab=\"Rust, 2024\",edition2024")
+        );
+        assert_eq!(
+            Some(("Shell".to_string(), "sh".to_string())),
+            split_tab_attribute("sh tab=Shell")
+        );
+        assert_eq!(None, split_tab_attribute("rust,notab=\"Rust\""));
+        assert_eq!(None, split_tab_attribute("rust"));
+    }
+
+    #[test]
+    fn test_parse_code_groups() -> Result<()> {
+        let content = "\
+        ```rust,tab=\"Rust\"\n\
+        let a = 1 + 2;\n\
+        ```\n\
+        \n\
+        ```python,tab=\"Python\"\n\
+        a = 1 + 2\n\
+        ```\n\
+        \n\
+        Text between code groups.\n\
+        \n\
+        {{#codegroup global=\"language\" }}\n\
+        \n\
+        ```rust\n\
+        let b = 3;\n\
+        ```\n\
+        \n\
+        ```js,tab=\"JavaScript\"\n\
+        const b = 3;\n\
+        ```\n\
+        \n\
+        {{#endcodegroup }}\n\
+        ";
+
+        let configs = parse_code_groups(content, None)?;
+        assert_eq!(2, configs.len());
+
+        let (span, tabs) = &configs[0];
+        assert_eq!(0..75, *span);
+        assert_eq!(None, tabs.global);
+        assert_eq!(
+            vec![
+                ("Rust", "```rust\nlet a = 1 + 2;\n```"),
+                ("Python", "```python\na = 1 + 2\n```")
+            ],
+            tabs.tabs
+                .iter()
+                .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        let (span, tabs) = &configs[1];
+        assert_eq!(
+            "{{#codegroup global=\"language\" }}",
+            content[span.clone()].lines().next().unwrap_or_default()
+        );
+        assert!(content[span.clone()].ends_with("{{#endcodegroup }}"));
+        assert_eq!(Some("language".to_string()), tabs.global);
+        assert_eq!(
+            vec![
+                ("rust", "```rust\nlet b = 3;\n```"),
+                ("JavaScript", "```js\nconst b = 3;\n```")
+            ],
+            tabs.tabs
+                .iter()
+                .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_code_groups_single() -> Result<()> {
+        // Code blocks with a `tab` attribute which are not adjacent to another one are not combined.
+        let content = "\
+        ```rust,tab=\"Rust\"\n\
+        let a = 1 + 2;\n\
+        ```\n\
+        \n\
+        Text between code blocks.\n\
+        \n\
+        ```python,tab=\"Python\"\n\
+        a = 1 + 2\n\
+        ```\n\
+        ";
+
+        assert!(parse_code_groups(content, None)?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use std::{fs, ops::Range, path::Path};
+
+use anyhow::{Context, Result, bail};
+use log::debug;
+use mdbook_preprocessor::{PreprocessorContext, book::Chapter};
+use serde::Deserialize;
+
+use crate::{
+    config::{TabConfig, TabsConfig},
+    files::location,
+    manifest::SourceMap,
+};
+
+/// Data file of a tabs group.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataFile {
+    tabs: Vec<DataTab>,
+}
+
+/// Tab of a data file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataTab {
+    name: String,
+    icon: Option<String>,
+    #[serde(default)]
+    content: String,
+    include: Option<String>,
+    lang: Option<String>,
+    lines: Option<String>,
+    anchor: Option<String>,
+    disabled: Option<bool>,
+    badge: Option<String>,
+}
+
+/// Parse a data file, based on its extension.
+fn parse_data_file(path: &Path) -> Result<DataFile> {
+    let content = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Ok(toml::from_str(&content)?),
+        Some("json") => Ok(serde_json::from_str(&content)?),
+        _ => bail!("Data file should be a TOML or JSON file."),
+    }
+}
+
+/// Load the tabs of a tabs group from its data file, relative to the book root.
+pub fn load_data_tabs(
+    ctx: &PreprocessorContext,
+    chapter: &Chapter,
+    source_map: &SourceMap,
+    span: &Range<usize>,
+    tabs: &mut TabsConfig,
+) -> Result<()> {
+    let Some(data) = &tabs.data else {
+        return Ok(());
+    };
+
+    let path = ctx.root.join(data);
+    if !path.exists() {
+        bail!(
+            "Data file `{data}` not found ({}).",
+            location(ctx, chapter, source_map, span.start)
+        );
+    }
+    debug!("Loading tabs from data file `{}`.", path.display());
+
+    let data_file = parse_data_file(&path).with_context(|| {
+        format!(
+            "Invalid data file `{data}` ({}).",
+            location(ctx, chapter, source_map, span.start)
+        )
+    })?;
+    let data_dir = path.parent().unwrap_or(&ctx.root);
+
+    for tab in data_file.tabs {
+        tabs.tabs.push((
+            TabConfig {
+                name: tab.name,
+                icon: tab.icon,
+                // Includes are relative to the data file.
+                include: tab
+                    .include
+                    .map(|include| data_dir.join(include).to_string_lossy().to_string()),
+                lang: tab.lang,
+                lines: tab.lines,
+                anchor: tab.anchor,
+                when: None,
+                label: None,
+                default: false,
+                disabled: tab.disabled,
+                badge: tab.badge,
+                span: span.clone(),
+            },
+            tab.content,
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::{chapter, context};
+
+    fn load(root: &Path, data: &str) -> Result<TabsConfig> {
+        let ctx = context(root, "", "html")?;
+        let chapter = chapter("a.md", "");
+
+        let mut tabs = TabsConfig {
+            data: Some(data.to_string()),
+            ..Default::default()
+        };
+        load_data_tabs(&ctx, &chapter, &SourceMap::new(""), &(0..0), &mut tabs)?;
+
+        Ok(tabs)
+    }
+
+    fn tab_names(tabs: &TabsConfig) -> Vec<(&str, &str)> {
+        tabs.tabs
+            .iter()
+            .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_load_data_tabs_toml() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir(root.path().join("data"))?;
+        fs::write(
+            root.path().join("data/install.toml"),
+            "\
+            [[tabs]]\n\
+            name = \"Linux\"\n\
+            content = \"Use **apt**.\"\n\
+            \n\
+            [[tabs]]\n\
+            name = \"Windows\"\n\
+            include = \"install.ps1\"\n\
+            lines = \"1-2\"\n\
+            disabled = true\n\
+            ",
+        )?;
+
+        let tabs = load(root.path(), "data/install.toml")?;
+        assert_eq!(
+            vec![("Linux", "Use **apt**."), ("Windows", "")],
+            tab_names(&tabs)
+        );
+
+        // Includes are relative to the data file.
+        let (windows, _) = &tabs.tabs[1];
+        assert_eq!(
+            Some(
+                root.path()
+                    .join("data/install.ps1")
+                    .to_string_lossy()
+                    .to_string()
+            ),
+            windows.include
+        );
+        assert_eq!(Some("1-2".to_string()), windows.lines);
+        assert!(windows.is_disabled());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_data_tabs_json() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::write(
+            root.path().join("install.json"),
+            r#"{"tabs": [{"name": "Linux", "icon": "brands linux", "content": "Use apt."}, {"name": "macOS"}]}"#,
+        )?;
+
+        let tabs = load(root.path(), "install.json")?;
+        assert_eq!(vec![("Linux", "Use apt."), ("macOS", "")], tab_names(&tabs));
+        assert_eq!(Some("brands linux".to_string()), tabs.tabs[0].0.icon);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_data_tabs_errors() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::write(root.path().join("invalid.toml"), "[[tabs]]\ncontent = 1\n")?;
+        fs::write(
+            root.path().join("unknown.json"),
+            r#"{"tabs": [{"name": "A", "color": "blue"}]}"#,
+        )?;
+        fs::write(root.path().join("install.yaml"), "tabs:\n  - name: Linux\n")?;
+
+        assert_eq!(
+            "Data file `missing.toml` not found (src/a.md:1).",
+            load(root.path(), "missing.toml").unwrap_err().to_string()
+        );
+
+        for data in ["invalid.toml", "unknown.json", "install.yaml"] {
+            assert_eq!(
+                format!("Invalid data file `{data}` (src/a.md:1)."),
+                load(root.path(), data).unwrap_err().to_string()
+            );
+        }
+
+        Ok(())
+    }
+}
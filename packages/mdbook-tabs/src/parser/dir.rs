@@ -0,0 +1,223 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use anyhow::{Result, bail};
+use glob::{MatchOptions, Pattern, glob_with};
+use log::debug;
+use mdbook_preprocessor::{PreprocessorContext, book::Chapter};
+
+use crate::{
+    config::{TabConfig, TabsConfig},
+    files::{location, resolve_path},
+    manifest::SourceMap,
+};
+
+/// Escape characters which have a meaning in inline Markdown, as tab labels are rendered as Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for character in text.chars() {
+        if matches!(character, '\\' | '`' | '*' | '_' | '[' | ']' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Load the tabs of a tabs group from the files in its directory which match its include patterns.
+///
+/// The files are sorted by pattern and then by path. Each file is included once, in the first matching pattern.
+pub fn load_dir_tabs(
+    ctx: &PreprocessorContext,
+    chapter: &Chapter,
+    source_map: &SourceMap,
+    span: &Range<usize>,
+    tabs: &mut TabsConfig,
+) -> Result<()> {
+    let Some(dir) = &tabs.dir else {
+        return Ok(());
+    };
+
+    let Some(dir_path) = resolve_path(ctx, chapter, dir).filter(|path| path.is_dir()) else {
+        bail!(
+            "Directory `{dir}` not found ({}).",
+            location(ctx, chapter, source_map, span.start)
+        );
+    };
+    debug!("Loading tabs from directory `{}`.", dir_path.display());
+
+    let patterns = tabs.include.clone().unwrap_or("*".to_string());
+    let options = MatchOptions {
+        require_literal_leading_dot: true,
+        ..Default::default()
+    };
+
+    let mut seen = BTreeSet::new();
+    for pattern in patterns.split(',').map(str::trim) {
+        let pattern = format!("{}/{pattern}", Pattern::escape(&dir_path.to_string_lossy()));
+        let mut paths = glob_with(&pattern, options)?.collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        for path in paths {
+            if !path.is_file() || !seen.insert(path.clone()) {
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(&dir_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+
+            tabs.tabs.push((
+                TabConfig {
+                    label: Some(escape_markdown(&name)),
+                    name,
+                    include: Some(path.to_string_lossy().to_string()),
+                    span: span.clone(),
+                    ..Default::default()
+                },
+                String::new(),
+            ));
+        }
+    }
+
+    if tabs.tabs.is_empty() {
+        bail!(
+            "No files in directory `{dir}` match `{patterns}` ({}).",
+            location(ctx, chapter, source_map, span.start)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::{chapter, context, preprocess};
+
+    fn load(root: &Path, dir: &str, include: Option<&str>) -> Result<TabsConfig> {
+        let ctx = context(root, "", "html")?;
+        let chapter = chapter("a.md", "");
+
+        let mut tabs = TabsConfig {
+            dir: Some(dir.to_string()),
+            include: include.map(|include| include.to_string()),
+            ..Default::default()
+        };
+        load_dir_tabs(&ctx, &chapter, &SourceMap::new(""), &(0..0), &mut tabs)?;
+
+        Ok(tabs)
+    }
+
+    #[test]
+    fn test_load_dir_tabs_escape() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir(root.path().join("example"))?;
+        fs::write(root.path().join("example/my_main.rs"), "fn main() {}")?;
+
+        let tabs = load(root.path(), "example", None)?;
+        let (tab, _) = &tabs.tabs[0];
+
+        assert_eq!("my_main.rs", tab.name);
+        assert_eq!("my\\_main.rs", tab.markdown_label());
+        assert_eq!("my_main.rs", crate::tabs::plain_label(tab));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dir_tabs() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let dir = root.path().join("examples/todo");
+        fs::create_dir_all(dir.join("src"))?;
+        fs::create_dir(dir.join("target"))?;
+        for file in [
+            "Cargo.toml",
+            "README.md",
+            ".hidden.rs",
+            "src/main.rs",
+            "src/app.rs",
+            "build.rs",
+        ] {
+            fs::write(dir.join(file), "")?;
+        }
+
+        let names = |tabs: TabsConfig| {
+            tabs.tabs
+                .into_iter()
+                .map(|(tab, _)| tab.name)
+                .collect::<Vec<_>>()
+        };
+
+        // Files are sorted by path, hidden files and directories are skipped.
+        assert_eq!(
+            vec!["Cargo.toml", "README.md", "build.rs"],
+            names(load(root.path(), "examples/todo", None)?)
+        );
+
+        // Files are sorted by pattern first and are included once.
+        assert_eq!(
+            vec!["src/app.rs", "src/main.rs", "build.rs", "Cargo.toml"],
+            names(load(
+                root.path(),
+                "examples/todo",
+                Some("src/*.rs,*.rs,src/main.rs,Cargo.toml")
+            )?)
+        );
+
+        let (tab, _) = &load(root.path(), "examples/todo", Some("src/main.rs"))?.tabs[0];
+        assert_eq!(
+            Some(dir.join("src/main.rs").to_string_lossy().to_string()),
+            tab.include
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dir_tabs_fenced() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir(root.path().join("example"))?;
+        fs::write(
+            root.path().join("example/README.md"),
+            "# Example\n\n```sh\ncargo run\n```\n",
+        )?;
+
+        // Code blocks in included Markdown files don't close the code block of the tab.
+        let content = preprocess(
+            &context(root.path(), "", "markdown")?,
+            "{{#tabs dir=\"example\" }}\n",
+        )?;
+        assert!(
+            content.contains("````md\n# Example\n\n```sh\ncargo run\n```\n````\n"),
+            "{content}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dir_tabs_errors() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        fs::create_dir(root.path().join("example"))?;
+        fs::write(root.path().join("example/main.rs"), "")?;
+
+        assert_eq!(
+            "No files in directory `example` match `*.toml` (src/a.md:1).",
+            load(root.path(), "example", Some("*.toml"))
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "Directory `missing` not found (src/a.md:1).",
+            load(root.path(), "missing", None).unwrap_err().to_string()
+        );
+
+        Ok(())
+    }
+}
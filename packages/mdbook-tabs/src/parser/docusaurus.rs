@@ -0,0 +1,349 @@
+use std::{collections::HashMap, ops::Range};
+
+use anyhow::{Result, bail};
+use log::debug;
+
+use super::{SpanAndTabs, fenced_code_block_spans};
+use crate::config::{TabConfig, TabsConfig};
+
+/// Parse the attributes of an HTML/JSX start tag, e.g. `<TabItem value="a" label="A" default>`.
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+
+    let mut rest = tag
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim_end_matches('/');
+    rest = rest
+        .find(char::is_whitespace)
+        .map(|index| &rest[index..])
+        .unwrap_or("");
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_string();
+        rest = &rest[name_end..];
+
+        match rest.strip_prefix('=') {
+            Some(value) => {
+                let (value, remainder) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = value[1..]
+                            .find(quote)
+                            .map(|end| end + 1)
+                            .unwrap_or(value.len());
+                        (&value[1..end], value.get(end + 1..).unwrap_or(""))
+                    }
+                    Some('{') => {
+                        let end = value.find('}').unwrap_or(value.len());
+                        (
+                            value[1..end].trim().trim_matches(['"', '\'', '`']),
+                            value.get(end + 1..).unwrap_or(""),
+                        )
+                    }
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+
+                attributes.insert(name, value.to_string());
+                rest = remainder;
+            }
+            None => {
+                attributes.insert(name, "true".to_string());
+            }
+        }
+    }
+
+    attributes
+}
+
+fn is_start_tag(line: &str, name: &str) -> bool {
+    line.trim_start()
+        .strip_prefix('<')
+        .and_then(|rest| rest.strip_prefix(name))
+        .is_some_and(|rest| rest.starts_with(['>', ' ', '\t']) || rest.is_empty())
+}
+
+fn is_end_tag(line: &str, name: &str) -> bool {
+    line.trim() == format!("</{name}>")
+}
+
+/// Remove the common indentation of lines.
+fn dedent(lines: &[&str]) -> String {
+    let indentation = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indentation..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Parse Docusaurus `<Tabs>` and `<TabItem>` blocks.
+pub fn parse_docusaurus_tabs(content: &str) -> Result<(Vec<SpanAndTabs>, bool)> {
+    let mut configs: Vec<SpanAndTabs> = vec![];
+    let mut has_nested = false;
+
+    let mut group: Option<(usize, TabsConfig)> = None;
+    let mut tab: Option<(TabConfig, Vec<&str>)> = None;
+    let mut nested_level = 0;
+
+    let code_block_spans = fenced_code_block_spans(content);
+
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let in_code_block = code_block_spans
+            .iter()
+            .any(|span| span.start <= start && start < span.end);
+
+        if group.is_none() {
+            if !in_code_block && is_start_tag(line, "Tabs") {
+                let attributes = parse_attributes(line);
+                group = Some((
+                    start,
+                    TabsConfig {
+                        global: attributes.get("groupId").cloned(),
+                        ..Default::default()
+                    },
+                ));
+            }
+            continue;
+        }
+
+        if nested_level == 0 && !in_code_block {
+            if is_start_tag(line, "TabItem") {
+                let (start_tag, rest) =
+                    line.split_at(line.find('>').map(|index| index + 1).unwrap_or(line.len()));
+
+                let attributes = parse_attributes(start_tag);
+                let Some(name) = attributes.get("value").or(attributes.get("label")) else {
+                    bail!(
+                        "Tab item `{}` should have a `value` or `label` attribute.",
+                        line.trim()
+                    );
+                };
+
+                let tab_config = TabConfig {
+                    name: name.clone(),
+                    label: attributes.get("label").cloned(),
+                    default: attributes
+                        .get("default")
+                        .is_some_and(|default| default != "false"),
+                    span: start..(start + line.len()),
+                    ..Default::default()
+                };
+
+                match rest.trim_end().strip_suffix("</TabItem>") {
+                    Some(inline_content) => {
+                        if let Some((_, tabs)) = group.as_mut() {
+                            tabs.tabs
+                                .push((tab_config, inline_content.trim().to_string()));
+                        }
+                    }
+                    None => {
+                        tab = Some((tab_config, vec![rest]));
+                    }
+                }
+                continue;
+            }
+
+            if is_end_tag(line, "TabItem") {
+                if let (Some((_, tabs)), Some((mut tab_config, lines))) =
+                    (group.as_mut(), tab.take())
+                {
+                    tab_config.span.end = start + line.len();
+                    tabs.tabs.push((tab_config, dedent(&lines)));
+                }
+                continue;
+            }
+
+            if is_end_tag(line, "Tabs") {
+                if let Some((group_start, tabs)) = group.take() {
+                    configs.push((group_start..(start + line.len()), tabs));
+                }
+                continue;
+            }
+        }
+
+        if !in_code_block {
+            if is_start_tag(line, "Tabs") {
+                nested_level += 1;
+                has_nested = true;
+            } else if is_end_tag(line, "Tabs") {
+                nested_level -= 1;
+            }
+        }
+
+        if let Some((_, lines)) = tab.as_mut() {
+            lines.push(line);
+        }
+    }
+
+    debug!("{configs:?}");
+
+    Ok((configs, has_nested))
+}
+
+/// Spans of the lines importing the Docusaurus tabs components, which are required in MDX but have to be removed.
+///
+/// Lines in fenced code blocks are kept, e.g. examples of the Docusaurus syntax.
+pub fn docusaurus_imports(content: &str) -> Vec<Range<usize>> {
+    let code_block_spans = fenced_code_block_spans(content);
+
+    let mut spans = vec![];
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let in_code_block = code_block_spans
+            .iter()
+            .any(|span| span.start <= offset && offset < span.end);
+
+        if !in_code_block
+            && trimmed.starts_with("import ")
+            && (trimmed.ends_with("from '@theme/Tabs';")
+                || trimmed.ends_with("from '@theme/TabItem';")
+                || trimmed.ends_with("from \"@theme/Tabs\";")
+                || trimmed.ends_with("from \"@theme/TabItem\";"))
+        {
+            spans.push(offset..offset + line.len());
+        }
+
+        offset += line.len();
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_docusaurus_tabs() -> Result<()> {
+        let content = "\
+        import Tabs from '@theme/Tabs';\n\
+        import TabItem from '@theme/TabItem';\n\
+        \n\
+        <Tabs groupId=\"os\">\n\
+        \x20 <TabItem value=\"linux\" label=\"Linux\">\n\
+        \x20   Some **Linux** content.\n\
+        \x20 </TabItem>\n\
+        \x20 <TabItem value=\"windows\" default>\n\
+        \x20   <Tabs>\n\
+        \x20     <TabItem value=\"nested\">Nested</TabItem>\n\
+        \x20   </Tabs>\n\
+        \x20 </TabItem>\n\
+        \x20 <TabItem value=\"inline\" label=\"Inline\">Inline content.</TabItem>\n\
+        </Tabs>\n\
+        ";
+
+        assert_eq!(vec![0..32, 32..70], docusaurus_imports(content));
+
+        let content = &content[70..];
+        let (configs, has_nested) = parse_docusaurus_tabs(content)?;
+
+        assert!(has_nested);
+        assert_eq!(1, configs.len());
+
+        let (span, tabs) = &configs[0];
+        assert_eq!(1..content.len() - 1, *span);
+        assert_eq!(Some("os".to_string()), tabs.global);
+        assert_eq!(
+            vec![
+                ("linux", "Some **Linux** content."),
+                (
+                    "windows",
+                    "<Tabs>\n  <TabItem value=\"nested\">Nested</TabItem>\n</Tabs>"
+                ),
+                ("inline", "Inline content."),
+            ],
+            tabs.tabs
+                .iter()
+                .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Some("Linux"), None, Some("Inline")],
+            tabs.tabs
+                .iter()
+                .map(|(tab, _)| tab.label.as_deref())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![false, true, false],
+            tabs.tabs
+                .iter()
+                .map(|(tab, _)| tab.default)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_docusaurus_tabs_code_block() -> Result<()> {
+        let content = "\
+        <Tabs>\n\
+        <TabItem value=\"markdown\">\n\
+        \n\
+        ````md\n\
+        ```\n\
+        </TabItem>\n\
+        ````\n\
+        \n\
+        </TabItem>\n\
+        </Tabs>\n\
+        ";
+
+        let (configs, _) = parse_docusaurus_tabs(content)?;
+        let (_, tabs) = &configs[0];
+
+        assert_eq!(
+            vec![("markdown", "````md\n```\n</TabItem>\n````")],
+            tabs.tabs
+                .iter()
+                .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_docusaurus_imports_in_code_block() {
+        let content = "\
+        import Tabs from '@theme/Tabs';\n\
+        \n\
+        ```md\n\
+        import Tabs from '@theme/Tabs';\n\
+        import TabItem from '@theme/TabItem';\n\
+        ```\n\
+        ";
+
+        assert_eq!(vec![0..32], docusaurus_imports(content));
+    }
+}
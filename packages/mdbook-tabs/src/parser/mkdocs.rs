@@ -0,0 +1,175 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use log::debug;
+
+use super::{SpanAndTabs, fenced_code_block_spans};
+use crate::config::{TabConfig, TabsConfig};
+
+/// Parse the tab name of a `=== "Name"` line.
+///
+/// Returns the name and whether the line starts a new group (`===!`).
+fn parse_tab_line(line: &str) -> Option<(String, bool)> {
+    let rest = line.strip_prefix("===")?;
+    let (rest, new_group) = match rest.chars().next() {
+        Some('!') => (&rest[1..], true),
+        Some('+') => (&rest[1..], false),
+        _ => (rest, false),
+    };
+
+    let name = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((name.to_string(), new_group))
+}
+
+/// Remove one level of indentation (four spaces or a tab) from a line.
+fn dedent(line: &str) -> Option<&str> {
+    line.strip_prefix("    ")
+        .or_else(|| line.strip_prefix('\t'))
+}
+
+/// Parse MkDocs Material content tabs (`=== "Name"` followed by indented content).
+pub fn parse_mkdocs_tabs(content: &str) -> Result<(Vec<SpanAndTabs>, bool)> {
+    let mut configs: Vec<SpanAndTabs> = vec![];
+    let mut has_nested = false;
+
+    let code_block_spans = fenced_code_block_spans(content);
+
+    let mut group: Option<(Range<usize>, TabsConfig)> = None;
+    let mut tab: Option<(TabConfig, Vec<&str>)> = None;
+
+    let finish_tab = |group: &mut Option<(Range<usize>, TabsConfig)>,
+                      tab: &mut Option<(TabConfig, Vec<&str>)>| {
+        if let (Some((_, tabs)), Some((tab, lines))) = (group.as_mut(), tab.take()) {
+            // Only blank lines are removed, as the indentation of the first line is significant.
+            let start = lines
+                .iter()
+                .position(|line| !line.trim().is_empty())
+                .unwrap_or(lines.len());
+            let end = lines
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(start, |index| index + 1);
+
+            tabs.tabs.push((tab, lines[start..end].join("\n")));
+        }
+    };
+
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if code_block_spans
+            .iter()
+            .any(|span| span.start <= start && start < span.end)
+        {
+            continue;
+        }
+
+        if let Some((name, new_group)) = parse_tab_line(line) {
+            finish_tab(&mut group, &mut tab);
+
+            if new_group && let Some(finished) = group.take() {
+                configs.push(finished);
+            }
+
+            let (span, _) = group.get_or_insert_with(|| (start..start, TabsConfig::default()));
+            span.end = start + line.len();
+
+            tab = Some((
+                TabConfig {
+                    name,
+                    span: start..(start + line.len()),
+                    ..Default::default()
+                },
+                vec![],
+            ));
+        } else if let Some((tab_config, lines)) = tab.as_mut() {
+            if line.trim().is_empty() {
+                lines.push("");
+            } else if let Some(dedented) = dedent(line) {
+                has_nested |= parse_tab_line(dedented).is_some();
+
+                lines.push(dedented);
+                tab_config.span.end = start + line.len();
+                if let Some((span, _)) = group.as_mut() {
+                    span.end = start + line.len();
+                }
+            } else {
+                finish_tab(&mut group, &mut tab);
+                configs.extend(group.take());
+            }
+        }
+    }
+
+    finish_tab(&mut group, &mut tab);
+    configs.extend(group.take());
+
+    debug!("{configs:?}");
+
+    Ok((configs, has_nested))
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_mkdocs_tabs() -> Result<()> {
+        let content = "\
+        === \"C\"\n\
+        \n\
+        \x20   ```c\n\
+        \x20   int a = 1;\n\
+        \x20   ```\n\
+        \n\
+        === \"C++\"\n\
+        \n\
+        \x20   Some text.\n\
+        \n\
+        \x20       Indented code.\n\
+        \n\
+        ===! \"Other\"\n\
+        \n\
+        \x20       Indented code.\n\
+        \n\
+        \x20   Other group.\n\
+        \n\
+        Text after the tabs.\n\
+        \n\
+        ```markdown\n\
+        === \"Ignored\"\n\
+        ```\n\
+        ";
+
+        let (configs, has_nested) = parse_mkdocs_tabs(content)?;
+        assert!(!has_nested);
+        assert_eq!(
+            vec![
+                vec![
+                    ("C", "```c\nint a = 1;\n```"),
+                    ("C++", "Some text.\n\n    Indented code.")
+                ],
+                vec![("Other", "    Indented code.\n\nOther group.")],
+            ],
+            configs
+                .iter()
+                .map(|(_, tabs)| tabs
+                    .tabs
+                    .iter()
+                    .map(|(tab, content)| (tab.name.as_str(), content.as_str()))
+                    .collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "=== \"C\"\n\n    ```c\n    int a = 1;\n    ```\n\n=== \"C++\"\n\n    Some text.\n\n        Indented code.",
+            &content[configs[0].0.clone()]
+        );
+
+        Ok(())
+    }
+}
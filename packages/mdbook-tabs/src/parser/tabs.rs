@@ -1,24 +1,40 @@
 use std::ops::Range;
 
-use anyhow::{Result, bail};
+use anyhow::Result;
 use log::debug;
 use mdbook_plugin_utils::markdown::parse_blocks;
-use mdbook_preprocessor::book::Chapter;
-use pulldown_cmark::Event;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
 
-use crate::config::{TabConfig, TabsConfig};
+use super::SpanAndTabs;
+use crate::{
+    conditions::{Vars, evaluate},
+    config::{TabConfig, TabsConfig},
+};
 
-fn is_tabs_start(event: &Event) -> bool {
+pub(crate) fn is_tabs_start(event: &Event) -> bool {
     match event {
         Event::Text(text) => {
             (text.to_string() == "{{#tabs}}" || text.starts_with("{{#tabs"))
                 && !text.contains("{{#endtabs")
+                && !is_standalone_tabs(text)
         }
         _ => false,
     }
 }
 
-fn is_tabs_end(event: &Event) -> bool {
+pub(crate) fn is_marker(text: &str, name: &str) -> bool {
+    text.strip_prefix(name)
+        .is_some_and(|rest| rest.starts_with("}}") || rest.starts_with(char::is_whitespace))
+}
+
+/// Whether the text is a tabs start marker of standalone tabs, which have no end marker.
+fn is_standalone_tabs(text: &str) -> bool {
+    is_marker(text, "{{#tabs")
+        && TabsConfig::parse(marker_arguments(text, 0, "{{#tabs").0)
+            .is_ok_and(|config| config.is_standalone())
+}
+
+pub(crate) fn is_tabs_end(event: &Event) -> bool {
     match event {
         Event::Text(text) => {
             (text.to_string() == "{{#endtabs}}" || text.starts_with("{{#endtabs "))
@@ -28,70 +44,78 @@ fn is_tabs_end(event: &Event) -> bool {
     }
 }
 
-fn is_tab_start(event: &Event) -> bool {
+pub(crate) fn is_tab_start(event: &Event) -> bool {
     match event {
         Event::Text(text) => text.to_string() == "{{#tab}}" || text.starts_with("{{#tab "),
         _ => false,
     }
 }
 
-fn is_tab_end(event: &Event) -> bool {
+pub(crate) fn is_tab_end(event: &Event) -> bool {
     match event {
         Event::Text(text) => text.to_string() == "{{#endtab}}" || text.starts_with("{{#endtab "),
         _ => false,
     }
 }
 
-type SpanAndTabs = (Range<usize>, TabsConfig);
+/// Arguments of the marker at an offset and the offset after the marker.
+///
+/// The arguments are read from the source, as Markdown in a tab name splits the marker into multiple events.
+pub(crate) fn marker_arguments<'a>(
+    content: &'a str,
+    offset: usize,
+    prefix: &str,
+) -> (&'a str, usize) {
+    let source = &content[offset..];
+    let end = source.find("}}").unwrap_or(source.len());
+
+    (
+        source[..end].trim_start_matches(prefix).trim(),
+        (offset + end + 2).min(content.len()),
+    )
+}
 
-pub fn parse_tabs(chapter: &Chapter) -> Result<(Vec<SpanAndTabs>, bool)> {
+/// Parse `{{#tabs}}` groups in Markdown, removing tabs whose `when` condition doesn't match the variables.
+///
+/// Returns the spans and configs of the outermost groups, and whether any group contains nested groups. Nested groups
+/// are part of the tab content and can be parsed after the outermost groups are replaced.
+pub fn parse_tabs(content: &str, vars: &Vars) -> Result<(Vec<SpanAndTabs>, bool)> {
     let mut configs: Vec<(Range<usize>, TabsConfig)> = vec![];
 
-    let blocks = parse_blocks(&chapter.content, is_tabs_start, is_tabs_end, true)?;
+    let blocks = parse_blocks(content, is_tabs_start, is_tabs_end, true)?;
     debug!("{blocks:?}");
 
     for block in &blocks {
-        let start_text = match &block.events[0].0 {
-            Event::Text(text) => text.to_string(),
-            _ => bail!("First event should be text."),
-        };
-
-        let mut tabs = TabsConfig::parse(
-            start_text
-                .trim_start_matches("{{#tabs")
-                .trim_start()
-                .trim_end_matches("}}")
-                .trim_end(),
-        )?;
-
-        let subblocks = parse_blocks(
-            &chapter.content[block.inner_span.clone()],
-            is_tab_start,
-            is_tab_end,
-            true,
-        )?;
+        let (arguments, marker_end) = marker_arguments(content, block.span.start, "{{#tabs");
+        let mut tabs = TabsConfig::parse(arguments)?;
+
+        let inner_start = block.inner_span.start.max(marker_end);
+        let inner_span = inner_start..block.inner_span.end.max(inner_start);
+        let subblocks = parse_blocks(&content[inner_span.clone()], is_tab_start, is_tab_end, true)?;
         debug!("{subblocks:?}");
 
+        let mut removed = false;
         for subblock in subblocks {
-            let start_text = match &subblock.events[0].0 {
-                Event::Text(text) => text.to_string(),
-                _ => bail!("First event should be text."),
-            };
-
-            tabs.tabs.push((
-                TabConfig::parse(
-                    start_text
-                        .trim_start_matches("{{#tab")
-                        .trim_start()
-                        .trim_end_matches("}}")
-                        .trim_end(),
-                )?,
-                chapter.content[(block.inner_span.start + subblock.inner_span.start)
-                    ..(block.inner_span.start + subblock.inner_span.end)]
-                    .to_string(),
-            ));
+            let (arguments, marker_end) =
+                marker_arguments(content, inner_span.start + subblock.span.start, "{{#tab");
+            let mut tab = TabConfig::parse(arguments)?;
+            if let Some(when) = &tab.when
+                && !evaluate(when, vars)?
+            {
+                removed = true;
+                continue;
+            }
+
+            tab.span =
+                (inner_span.start + subblock.span.start)..(inner_span.start + subblock.span.end);
+
+            let content_start = (inner_span.start + subblock.inner_span.start).max(marker_end);
+            let content_end = (inner_span.start + subblock.inner_span.end).max(content_start);
+            tabs.tabs
+                .push((tab, content[content_start..content_end].to_string()));
         }
 
+        tabs.collapse = removed && tabs.tabs.len() <= 1;
         configs.push((block.span.clone(), tabs));
     }
 
@@ -99,3 +123,178 @@ pub fn parse_tabs(chapter: &Chapter) -> Result<(Vec<SpanAndTabs>, bool)> {
 
     Ok((configs, blocks.iter().any(|block| block.has_nested)))
 }
+
+/// Parse the start markers of standalone tabs, e.g. `{{#tabs data="..." }}`, without loading their tabs.
+pub fn parse_standalone_tabs(content: &str) -> Result<Vec<SpanAndTabs>> {
+    let mut configs = vec![];
+    let mut code_block_level = 0;
+
+    for (event, span) in Parser::new(content).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code_block_level += 1,
+            Event::End(TagEnd::CodeBlock) => code_block_level -= 1,
+            Event::Text(text) if code_block_level == 0 && is_marker(&text, "{{#tabs") => {
+                // Markdown in the arguments (e.g. `*` in a pattern) splits the text, so parse the source instead.
+                let (arguments, marker_end) = marker_arguments(content, span.start, "{{#tabs");
+
+                if let Ok(config) = TabsConfig::parse(arguments)
+                    && config.is_standalone()
+                {
+                    configs.push((span.start..marker_end, config));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    debug!("{configs:?}");
+
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_tabs_markdown_name() -> Result<()> {
+        let content = "\
+        {{#tabs }}\n\
+        {{#tab name=\"`cargo`\",icon=\"terminal\" }}\n\
+        cargo add serde\n\
+        {{#endtab }}\n\
+        {{#tab name=\"*Other*\" }}\n\
+        Other content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        let (configs, has_nested) = parse_tabs(content, &Vars::default())?;
+        assert!(!has_nested);
+        assert_eq!(1, configs.len());
+
+        let tabs = &configs[0].1.tabs;
+        assert_eq!(
+            vec![
+                ("`cargo`", Some("terminal"), "cargo add serde"),
+                ("*Other*", None, "Other content")
+            ],
+            tabs.iter()
+                .map(|(tab, content)| (tab.name.as_str(), tab.icon.as_deref(), content.trim()))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tabs_unknown_arguments() -> Result<()> {
+        let content = "\
+        {{#tabs global=\"os\",color=\"blue\" }}\n\
+        {{#tab name=\"Linux\",sticky }}\n\
+        Linux content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        // Unknown arguments are ignored, so they are only reported by `mdbook-tabs check`.
+        let (configs, _) = parse_tabs(content, &Vars::default())?;
+        assert_eq!(1, configs.len());
+        assert_eq!(Some("os".to_string()), configs[0].1.global);
+        assert_eq!("Linux", configs[0].1.tabs[0].0.name);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_standalone_tabs() -> Result<()> {
+        let content = "\
+        {{#tabs dir=\"examples/todo\",include=\"*.rs,Cargo.toml\" }}\n\
+        \n\
+        {{#tabs data=\"install.toml\" }}\n\
+        \n\
+        {{#tabs }}\n\
+        {{#endtabs }}\n\
+        \n\
+        ```markdown\n\
+        {{#tabs data=\"ignored.toml\" }}\n\
+        ```\n\
+        ";
+
+        let configs = parse_standalone_tabs(content)?;
+        assert_eq!(
+            vec![
+                (
+                    "{{#tabs dir=\"examples/todo\",include=\"*.rs,Cargo.toml\" }}",
+                    Some("examples/todo"),
+                    Some("*.rs,Cargo.toml"),
+                    None
+                ),
+                (
+                    "{{#tabs data=\"install.toml\" }}",
+                    None,
+                    None,
+                    Some("install.toml")
+                ),
+            ],
+            configs
+                .iter()
+                .map(|(span, config)| (
+                    &content[span.clone()],
+                    config.dir.as_deref(),
+                    config.include.as_deref(),
+                    config.data.as_deref()
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tabs_when() -> Result<()> {
+        let content = "\
+        {{#tabs }}\n\
+        {{#tab name=\"Cloud\",when=\"edition=cloud\" }}\n\
+        Cloud content\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Self-hosted\",when=\"edition=self-hosted\" }}\n\
+        Self-hosted content\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Other\" }}\n\
+        Other content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        \n\
+        {{#tabs }}\n\
+        {{#tab name=\"Cloud\",when=\"edition=cloud\" }}\n\
+        Cloud content\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Self-hosted\",when=\"edition!=cloud\" }}\n\
+        Self-hosted content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+        let vars = Vars::new([("edition".to_string(), "cloud".to_string())].into());
+
+        let (configs, _) = parse_tabs(content, &vars)?;
+        assert_eq!(
+            vec![(vec!["Cloud", "Other"], false), (vec!["Cloud"], true)],
+            configs
+                .iter()
+                .map(|(_, config)| (
+                    config
+                        .tabs
+                        .iter()
+                        .map(|(tab, _)| tab.name.as_str())
+                        .collect::<Vec<_>>(),
+                    config.collapse
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -1,18 +1,43 @@
-use std::str;
+use std::{fs, ops::Range, str};
 
 use anyhow::{Result, bail};
 use mdbook_preprocessor::{
     Preprocessor, PreprocessorContext,
-    book::{Book, BookItem},
+    book::{Book, BookItem, Chapter},
 };
 
-use crate::{parser::tabs::parse_tabs, tabs::tabs};
+use crate::{
+    conditions::Vars,
+    config::{FallbackConfig, PreprocessorConfig, Syntax, TabsConfig},
+    files::{include, location},
+    headings::{id_from_content, prefix_heading_ids},
+    manifest::{Manifest, ManifestGroup, ManifestTab, SourceMap},
+    parser::{
+        code_group::parse_code_groups,
+        data::load_data_tabs,
+        dir::load_dir_tabs,
+        docusaurus::{docusaurus_imports, parse_docusaurus_tabs},
+        mkdocs::parse_mkdocs_tabs,
+        tabs::{parse_standalone_tabs, parse_tabs},
+    },
+    tabs::{HtmlTabsRenderer, TabsRenderer, markdown, plain_label},
+};
 
-pub struct TabsPreprocessor;
+pub struct TabsPreprocessor {
+    renderer: Option<Box<dyn TabsRenderer>>,
+}
 
 impl TabsPreprocessor {
+    /// Create the preprocessor with the default renderer, which uses the templates of the book config.
     pub fn new() -> Self {
-        Self
+        Self { renderer: None }
+    }
+
+    /// Create the preprocessor with a custom renderer for the `html` renderer.
+    pub fn with_renderer<R: TabsRenderer + 'static>(renderer: R) -> Self {
+        Self {
+            renderer: Some(Box::new(renderer)),
+        }
     }
 }
 
@@ -27,10 +52,49 @@ impl Preprocessor for TabsPreprocessor {
         "tabs"
     }
 
-    fn run(&self, _ctx: &PreprocessorContext, book: Book) -> Result<Book> {
+    fn run(&self, ctx: &PreprocessorContext, book: Book) -> Result<Book> {
         let mut book = book.clone();
 
-        process_items(&mut book.items)?;
+        let config = ctx
+            .config
+            .get::<PreprocessorConfig>("preprocessor.tabs")?
+            .unwrap_or_default();
+
+        let mut vars = config.vars.clone();
+        vars.insert("renderer".to_string(), ctx.renderer.clone());
+
+        let mut manifest = Manifest {
+            renderer: ctx.renderer.clone(),
+            groups: vec![],
+        };
+
+        let default_renderer;
+        let renderer = match &self.renderer {
+            Some(renderer) => renderer.as_ref(),
+            None => {
+                default_renderer = HtmlTabsRenderer::new(&config.templates, &ctx.root)?;
+                &default_renderer
+            }
+        };
+
+        process_items(
+            ctx,
+            &config,
+            renderer,
+            &Vars::new(vars),
+            &mut manifest,
+            &mut book.items,
+        )?;
+
+        // The preprocessor runs once per renderer, so the manifest is only written for the HTML renderer.
+        if let Some(manifest_path) = config.manifest.as_ref().filter(|_| ctx.renderer == "html") {
+            let path = ctx.root.join(manifest_path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+        }
 
         Ok(book)
     }
@@ -40,38 +104,434 @@ impl Preprocessor for TabsPreprocessor {
     }
 }
 
-fn process_items(items: &mut Vec<BookItem>) -> Result<()> {
+/// State of the chapter being processed.
+struct ChapterState {
+    id_prefix: String,
+    index: usize,
+    source_map: SourceMap,
+    groups: Vec<ManifestGroup>,
+}
+
+impl ChapterState {
+    fn new(chapter: &Chapter) -> Self {
+        Self {
+            id_prefix: chapter_id_prefix(chapter),
+            index: 0,
+            source_map: SourceMap::new(&chapter.content),
+            groups: vec![],
+        }
+    }
+
+    fn replace(&mut self, chapter: &mut Chapter, range: Range<usize>, replacement: &str) {
+        chapter.content.replace_range(range.clone(), replacement);
+        self.source_map.edit(range, replacement.len());
+    }
+}
+
+/// Prefix of the IDs of tabs in a chapter, based on its path.
+///
+/// IDs are unique within the book, so they don't collide on the print page, which combines all chapters. Different paths
+/// can have the same readable name (e.g. `guide/install.md` and `guide-install.md`), so a hash of the path is included.
+fn chapter_id_prefix(chapter: &Chapter) -> String {
+    let (path, name) = match &chapter.source_path {
+        Some(path) => (
+            path.to_string_lossy().replace('\\', "/"),
+            path.with_extension("")
+                .to_string_lossy()
+                .replace(['/', '\\', '.'], " "),
+        ),
+        None => (chapter.name.clone(), chapter.name.clone()),
+    };
+
+    format!(
+        "mdbook-tabs-{}-{:08x}",
+        id_from_content(&name),
+        path_hash(&path)
+    )
+}
+
+/// Stable hash (FNV-1a) of a path, so IDs don't change between builds.
+fn path_hash(path: &str) -> u32 {
+    path.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    })
+}
+
+fn process_items(
+    ctx: &PreprocessorContext,
+    config: &PreprocessorConfig,
+    renderer: &dyn TabsRenderer,
+    vars: &Vars,
+    manifest: &mut Manifest,
+    items: &mut Vec<BookItem>,
+) -> Result<()> {
     for section in items {
         if let BookItem::Chapter(chapter) = section {
-            loop {
-                let (configs, has_nested) = parse_tabs(chapter)?;
+            let mut state = ChapterState::new(chapter);
 
-                let mut offset: isize = 0;
+            let mut configs = parse_standalone_tabs(&chapter.content)?;
+            for (span, tabs_config) in &mut configs {
+                if tabs_config.data.is_some() && tabs_config.dir.is_some() {
+                    bail!(
+                        "Tabs can't have both `data` and `dir` ({}).",
+                        location(ctx, chapter, &state.source_map, span.start)
+                    );
+                }
 
-                for (span, config) in configs {
-                    let replacement = tabs(&config);
+                load_data_tabs(ctx, chapter, &state.source_map, span, tabs_config)?;
+                load_dir_tabs(ctx, chapter, &state.source_map, span, tabs_config)?;
+            }
+            replace_tabs(ctx, config, renderer, chapter, &mut state, configs, 0)?;
 
-                    let start = span.start as isize + offset;
-                    let end = span.end as isize + offset;
-                    if start < 0 || end < 0 {
-                        bail!("Negative range {}..{}.", start, end);
+            for syntax in &config.syntaxes {
+                match syntax {
+                    Syntax::Mkdocs => {
+                        replace_nested_tabs(
+                            ctx,
+                            config,
+                            renderer,
+                            chapter,
+                            &mut state,
+                            |chapter| parse_mkdocs_tabs(&chapter.content),
+                        )?;
                     }
+                    Syntax::Docusaurus => {
+                        for span in docusaurus_imports(&chapter.content).into_iter().rev() {
+                            state.replace(chapter, span, "");
+                        }
 
-                    chapter
-                        .content
-                        .replace_range(start as usize..end as usize, &replacement);
-
-                    offset += replacement.len() as isize - span.len() as isize;
+                        replace_nested_tabs(
+                            ctx,
+                            config,
+                            renderer,
+                            chapter,
+                            &mut state,
+                            |chapter| parse_docusaurus_tabs(&chapter.content),
+                        )?;
+                    }
                 }
+            }
 
-                if !has_nested {
-                    break;
-                }
+            let configs = parse_code_groups(&chapter.content, config.code_group_global.as_ref())?;
+            replace_tabs(ctx, config, renderer, chapter, &mut state, configs, 0)?;
+
+            replace_nested_tabs(ctx, config, renderer, chapter, &mut state, |chapter| {
+                parse_tabs(&chapter.content, vars)
+            })?;
+
+            manifest.groups.append(&mut state.groups);
+
+            process_items(
+                ctx,
+                config,
+                renderer,
+                vars,
+                manifest,
+                &mut chapter.sub_items,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn replace_nested_tabs<ParseFn>(
+    ctx: &PreprocessorContext,
+    config: &PreprocessorConfig,
+    renderer: &dyn TabsRenderer,
+    chapter: &mut Chapter,
+    state: &mut ChapterState,
+    parse: ParseFn,
+) -> Result<()>
+where
+    ParseFn: Fn(&Chapter) -> Result<(Vec<(Range<usize>, TabsConfig)>, bool)>,
+{
+    // Each iteration replaces the groups of the next nesting level.
+    let mut depth = 0;
+    loop {
+        let (configs, has_nested) = parse(chapter)?;
+        let is_empty = configs.is_empty();
+
+        replace_tabs(ctx, config, renderer, chapter, state, configs, depth)?;
+
+        if !has_nested || is_empty {
+            break;
+        }
+        depth += 1;
+    }
+
+    Ok(())
+}
+
+fn replace_tabs(
+    ctx: &PreprocessorContext,
+    config: &PreprocessorConfig,
+    renderer: &dyn TabsRenderer,
+    chapter: &mut Chapter,
+    state: &mut ChapterState,
+    mut configs: Vec<(Range<usize>, TabsConfig)>,
+    depth: u8,
+) -> Result<()> {
+    for (span, tabs_config) in &mut configs {
+        tabs_config.id = format!("{}-{}", state.id_prefix, state.index);
+        state.index += 1;
+
+        for (tab, tab_content) in &mut tabs_config.tabs {
+            *tab_content = include(ctx, chapter, &state.source_map, tab, tab_content)?;
+
+            if config.prefix_heading_ids && !tabs_config.collapse {
+                *tab_content = prefix_heading_ids(tab_content, &tab.key());
             }
+        }
 
-            process_items(&mut chapter.sub_items)?;
+        if !tabs_config.collapse {
+            state.groups.push(manifest_group(
+                chapter,
+                &state.source_map,
+                span,
+                tabs_config,
+            ));
         }
     }
 
+    let mut offset: isize = 0;
+
+    for (span, tabs_config) in configs {
+        let replacement = if tabs_config.collapse {
+            tabs_config
+                .tabs
+                .iter()
+                .map(|(_, tab_content)| format!("\n\n{}\n\n", tab_content.trim()))
+                .collect()
+        } else if ctx.renderer == "html" {
+            renderer.render(&tabs_config, config)?
+        } else {
+            // Headings of nested groups are one level lower than the headings of their parent group.
+            let fallback = FallbackConfig {
+                heading_level: config.fallback.heading_level.saturating_add(depth),
+                ..config.fallback.clone()
+            };
+            markdown(&tabs_config, &fallback)
+        };
+
+        let start = span.start as isize + offset;
+        let end = span.end as isize + offset;
+        if start < 0 || end < 0 {
+            bail!("Negative range {}..{}.", start, end);
+        }
+
+        state.replace(chapter, start as usize..end as usize, &replacement);
+
+        offset += replacement.len() as isize - span.len() as isize;
+    }
+
     Ok(())
 }
+
+fn manifest_group(
+    chapter: &Chapter,
+    source_map: &SourceMap,
+    span: &Range<usize>,
+    tabs_config: &TabsConfig,
+) -> ManifestGroup {
+    let span = source_map.source_range(span);
+
+    ManifestGroup {
+        chapter: chapter.source_path.clone(),
+        id: tabs_config.id.clone(),
+        global: tabs_config.global.clone(),
+        line: source_map.line(span.start),
+        span,
+        tabs: tabs_config
+            .tabs
+            .iter()
+            .map(|(tab, _)| {
+                let span = source_map.source_range(&tab.span);
+
+                ManifestTab {
+                    key: tab.name.clone(),
+                    label: plain_label(tab),
+                    line: source_map.line(span.start),
+                    span,
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::{chapter, context, preprocess};
+
+    #[test]
+    fn test_markdown_fallback() -> Result<()> {
+        let content = "\
+        # A\n\
+        \n\
+        {{#tabs }}\n\
+        {{#tab name=\"Linux\" }}\n\
+        Linux content\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Windows\" }}\n\
+        Windows content\n\
+        \n\
+        More content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        for (config, expected) in [
+            (
+                "",
+                "#### Linux\n\nLinux content\n\n#### Windows\n\nWindows content\n\nMore content\n",
+            ),
+            (
+                "[preprocessor.tabs.fallback]\nformat = \"definition-list\"\n",
+                "Linux\n:   Linux content\n\nWindows\n:   Windows content\n\n    More content\n",
+            ),
+        ] {
+            let root = tempfile::tempdir()?;
+            let ctx = context(root.path(), config, "markdown")?;
+
+            assert_eq!(format!("# A\n\n{expected}\n"), preprocess(&ctx, content)?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_markdown_fallback_nested() -> Result<()> {
+        let content = "\
+        {{#tabs }}\n\
+        {{#tab name=\"a <b> c\" }}\n\
+        {{#tabs }}\n\
+        {{#tab name=\"Nested\" }}\n\
+        Nested content\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        let root = tempfile::tempdir()?;
+        let ctx = context(root.path(), "", "markdown")?;
+
+        assert_eq!(
+            "#### a &lt;b&gt; c\n\n##### Nested\n\nNested content\n\n\n",
+            preprocess(&ctx, content)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_heading_ids_nested() -> Result<()> {
+        let content = "\
+        {{#tabs }}\n\
+        {{#tab name=\"Linux\" }}\n\
+        {{#tabs }}\n\
+        {{#tab name=\"CLI\" }}\n\
+        ## Steps\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        {{#endtab }}\n\
+        {{#tab name=\"Windows\" }}\n\
+        {{#tabs }}\n\
+        {{#tab name=\"CLI\" }}\n\
+        ## Steps\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        let root = tempfile::tempdir()?;
+        let ctx = context(
+            root.path(),
+            "[preprocessor.tabs]\nprefix-heading-ids = true\n",
+            "markdown",
+        )?;
+        let content = preprocess(&ctx, content)?;
+
+        assert!(content.contains("## Steps {#linux-cli-steps}"), "{content}");
+        assert!(
+            content.contains("## Steps {#windows-cli-steps}"),
+            "{content}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chapter_id_prefix() {
+        let prefix = |path: &str| chapter_id_prefix(&chapter(path, ""));
+
+        assert_eq!(
+            "mdbook-tabs-guide-install-7e3dd952",
+            prefix("guide/install.md")
+        );
+        assert_ne!(prefix("guide/install.md"), prefix("guide-install.md"));
+        assert_ne!(prefix("guide/install.md"), prefix("guide.install.md"));
+    }
+
+    #[test]
+    fn test_manifest() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let config = "[preprocessor.tabs]\nmanifest = \"target/tabs.json\"\n";
+        let content = "\
+        {{#tabs global=\"os\" }}\n\
+        {{#tab name=\"Linux\" }}\n\
+        Linux.\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+        let path = root.path().join("target/tabs.json");
+
+        // Other renderers don't overwrite the manifest of the HTML renderer.
+        preprocess(&context(root.path(), config, "markdown")?, content)?;
+        assert!(!path.exists());
+
+        preprocess(&context(root.path(), config, "html")?, content)?;
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!("html", manifest["renderer"]);
+        assert_eq!("os", manifest["groups"][0]["global"]);
+        assert_eq!("Linux", manifest["groups"][0]["tabs"][0]["key"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_location() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let ctx = context(root.path(), "", "html")?;
+
+        let content = "\
+        # A\n\
+        \n\
+        ```rust,tab=\"Rust\"\n\
+        fn main() {}\n\
+        ```\n\
+        \n\
+        ```python,tab=\"Python\"\n\
+        print()\n\
+        ```\n\
+        \n\
+        {{#tabs }}\n\
+        {{#tab name=\"Missing\",include=\"missing.rs\" }}\n\
+        {{#endtab }}\n\
+        {{#endtabs }}\n\
+        ";
+
+        let err = preprocess(&ctx, content).expect_err("Include should fail.");
+
+        assert_eq!(
+            "File `missing.rs` included by tab `Missing` not found (src/a.md:12).",
+            err.to_string()
+        );
+
+        Ok(())
+    }
+}
@@ -1,42 +1,538 @@
-use crate::config::TabsConfig;
-
-pub fn tabs(config: &TabsConfig) -> String {
-    format!(
-        "<div class=\"mdbook-tabs-container\"{}>\n<nav class=\"mdbook-tabs\">\n{}\n</nav>\n{}\n</div>",
-        config
-            .global
-            .as_ref()
-            .map(|global| format!(" data-tabglobal=\"{global}\""))
-            .unwrap_or("".to_string()),
-        config
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, bail};
+use handlebars::Handlebars;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd, html::push_html};
+use pulldown_cmark_escape::escape_html;
+use serde::Serialize;
+
+use crate::config::{
+    FallbackConfig, FallbackFormat, Mode, PreprocessorConfig, Storage, Style, TabConfig,
+    TabsConfig, TemplatesConfig,
+};
+
+/// Renders tabs as HTML for the `html` renderer.
+pub trait TabsRenderer {
+    /// Render tabs. The content of each tab is Markdown, so it should be surrounded by blank lines.
+    ///
+    /// The `id` of the config is assigned by the preprocessor and is unique within the book.
+    fn render(
+        &self,
+        config: &TabsConfig,
+        preprocessor_config: &PreprocessorConfig,
+    ) -> Result<String>;
+}
+
+/// Default renderer, which renders tabs with Handlebars templates.
+///
+/// The default templates render the markup used by `tabs.css` and `tabs.js`.
+#[derive(Clone, Debug)]
+pub struct HtmlTabsRenderer {
+    handlebars: Handlebars<'static>,
+}
+
+impl HtmlTabsRenderer {
+    /// Create the renderer with the templates of `[preprocessor.tabs.templates]`, relative to the book root.
+    pub fn new(templates: &TemplatesConfig, root: &Path) -> Result<Self> {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(escape);
+
+        for (name, path, default) in [
+            ("container", &templates.container, CONTAINER_TEMPLATE),
+            ("button", &templates.button, BUTTON_TEMPLATE),
+            ("panel", &templates.panel, PANEL_TEMPLATE),
+        ] {
+            let template = match path {
+                Some(path) => fs::read_to_string(root.join(path)).with_context(|| {
+                    format!("Failed to read {name} template `{}`.", path.display())
+                })?,
+                None => default.to_string(),
+            };
+
+            handlebars
+                .register_template_string(name, template)
+                .with_context(|| format!("Invalid {name} template."))?;
+        }
+
+        Ok(Self { handlebars })
+    }
+
+    fn render_template<T: Serialize>(&self, name: &str, data: &T) -> Result<String> {
+        let html = self
+            .handlebars
+            .render(name, data)
+            .with_context(|| format!("Failed to render {name} template."))?;
+
+        // Blank lines would end the HTML block in Markdown, so trailing newlines of templates are removed.
+        Ok(html.trim_end_matches('\n').to_string())
+    }
+}
+
+impl Default for HtmlTabsRenderer {
+    fn default() -> Self {
+        Self::new(&TemplatesConfig::default(), Path::new(""))
+            .expect("Default templates should be valid.")
+    }
+}
+
+impl TabsRenderer for HtmlTabsRenderer {
+    fn render(
+        &self,
+        config: &TabsConfig,
+        preprocessor_config: &PreprocessorConfig,
+    ) -> Result<String> {
+        // The ID connects the buttons to the panels, so tabs without an ID wouldn't switch.
+        if config.id.is_empty() {
+            bail!("Tabs group should have an `id`.");
+        }
+
+        let mode = config.mode.unwrap_or(preprocessor_config.mode);
+        let style = config.style.unwrap_or(preprocessor_config.style);
+        let class_prefix = preprocessor_config.class_prefix();
+
+        // Disabled tabs can't be selected, so the first enabled default tab or enabled tab is initially active.
+        let active = config
             .tabs
             .iter()
-            .enumerate()
-            .map(|(index, (tab, _))| format!(
-                "<button class=\"mdbook-tab{}\" data-tabname=\"{}\">{}</button>",
-                match index == 0 {
-                    true => " active",
-                    false => "",
-                },
-                tab.name,
-                tab.name
-            ))
-            .collect::<Vec<_>>()
-            .join("\n"),
-        config
+            .position(|(tab, _)| tab.is_default() && !tab.is_disabled())
+            .or_else(|| config.tabs.iter().position(|(tab, _)| !tab.is_disabled()))
+            .unwrap_or(0);
+
+        let tabs = config
             .tabs
             .iter()
             .enumerate()
-            .map(|(index, (tab, tab_content))| format!(
-                "<div class=\"mdbook-tab-content{}\" data-tabname=\"{}\">\n\n{}\n\n</div>",
-                match index == 0 {
-                    true => "",
-                    false => " hidden",
-                },
-                tab.name,
+            .map(|(index, (tab, tab_content))| {
+                let context = TabContext {
+                    class_prefix,
+                    id: &config.id,
+                    css: mode == Mode::Css,
+                    index,
+                    active: index == active,
+                    disabled: tab.is_disabled(),
+                    name: &tab.name,
+                    icon: tab.icon.as_deref(),
+                    badge: tab.badge.as_deref(),
+                    label: label(tab),
+                    plain_label: plain_label(tab),
+                    content: tab_content,
+                };
+
+                Ok(RenderedTab {
+                    button: self.render_template("button", &context)?,
+                    panel: self.render_template("panel", &context)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.render_template(
+            "container",
+            &ContainerContext {
+                class_prefix,
+                id: &config.id,
+                css: mode == Mode::Css,
+                mode,
+                style,
+                global: config.global.as_deref(),
+                classes: classes(class_prefix, mode, style),
+                attributes: attributes(config, preprocessor_config, mode, style),
+                show_all: config.show_all.unwrap_or(false),
+                tabs,
+            },
+        )
+    }
+}
+
+const CONTAINER_TEMPLATE: &str = include_str!("templates/container.hbs");
+const BUTTON_TEMPLATE: &str = include_str!("templates/button.hbs");
+const PANEL_TEMPLATE: &str = include_str!("templates/panel.hbs");
+
+/// Data of the container template.
+#[derive(Serialize)]
+struct ContainerContext<'a> {
+    class_prefix: &'a str,
+    id: &'a str,
+    css: bool,
+    mode: Mode,
+    style: Style,
+    global: Option<&'a str>,
+    classes: String,
+    attributes: String,
+    show_all: bool,
+    tabs: Vec<RenderedTab>,
+}
+
+/// Rendered button and panel of a tab in the container template.
+#[derive(Serialize)]
+struct RenderedTab {
+    button: String,
+    panel: String,
+}
+
+/// Data of the button and panel templates.
+#[derive(Serialize)]
+struct TabContext<'a> {
+    class_prefix: &'a str,
+    id: &'a str,
+    css: bool,
+    index: usize,
+    active: bool,
+    disabled: bool,
+    name: &'a str,
+    icon: Option<&'a str>,
+    badge: Option<&'a str>,
+    label: String,
+    plain_label: String,
+    content: &'a str,
+}
+
+fn classes(class_prefix: &str, mode: Mode, style: Style) -> String {
+    let mut classes = vec![format!("{class_prefix}-tabs-container")];
+    if mode == Mode::Css {
+        classes.push(format!("{class_prefix}-tabs-css"));
+    }
+    match style {
+        Style::Horizontal => {}
+        Style::Vertical => classes.push(format!("{class_prefix}-tabs-vertical")),
+        Style::Pills => classes.push(format!("{class_prefix}-tabs-pills")),
+    }
+
+    classes.join(" ")
+}
+
+/// Data attributes of the container, which configure `tabs.js`.
+fn attributes(
+    config: &TabsConfig,
+    preprocessor_config: &PreprocessorConfig,
+    mode: Mode,
+    style: Style,
+) -> String {
+    let mut attributes = String::new();
+    if let Some(global) = &config.global {
+        attributes.push_str(&format!(" data-tabglobal=\"{}\"", escape(global)));
+    }
+    if let Some(width) = config
+        .dropdown_width
+        .or(preprocessor_config.dropdown_width)
+        .filter(|width| *width > 0)
+    {
+        attributes.push_str(&format!(" data-tabdropdown=\"{width}\""));
+    }
+    match preprocessor_config.storage {
+        Storage::Local => {}
+        Storage::Session => attributes.push_str(" data-tabstorage=\"session\""),
+        Storage::Url => attributes.push_str(" data-tabstorage=\"url\""),
+        Storage::None => attributes.push_str(" data-tabstorage=\"none\""),
+    }
+    if let Some(namespace) = &preprocessor_config.storage_namespace {
+        attributes.push_str(&format!(" data-tabnamespace=\"{}\"", escape(namespace)));
+    }
+    if preprocessor_config.remember_per_page {
+        attributes.push_str(" data-tabremember");
+    }
+    if mode == Mode::Css && style == Style::Vertical {
+        // Vertical CSS-only tabs place every panel next to all labels, which requires the tab count.
+        attributes.push_str(&format!(
+            " style=\"--mdbook-tabs-count: {}\"",
+            config.tabs.len()
+        ));
+    }
+
+    attributes
+}
+
+/// Escape text for use in HTML content or attributes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    escape_html(&mut escaped, text).expect("Writing to a string should not fail.");
+    escaped
+}
+
+/// Font Awesome classes of an icon, e.g. `linux` or `brands linux`.
+fn icon_classes(icon: &str) -> String {
+    let mut classes = icon
+        .split_whitespace()
+        .map(|class| match class {
+            "fa" | "fas" | "far" | "fab" => class.to_string(),
+            class if class.starts_with("fa-") => class.to_string(),
+            class => format!("fa-{class}"),
+        })
+        .collect::<Vec<_>>();
+
+    if !classes.iter().any(|class| {
+        matches!(
+            class.as_str(),
+            "fa" | "fas" | "far" | "fab" | "fa-regular" | "fa-solid" | "fa-brands"
+        )
+    }) {
+        classes.insert(0, "fa-solid".to_string());
+    }
+
+    classes.join(" ")
+}
+
+/// Events of a tab label, if it is inline Markdown.
+fn label_events(tab: &TabConfig) -> Option<Vec<Event<'_>>> {
+    let events = Parser::new(tab.markdown_label()).collect::<Vec<_>>();
+
+    match events.first() {
+        Some(Event::Start(Tag::Paragraph)) => Some(
+            events
+                .into_iter()
+                .filter(|event| {
+                    !matches!(
+                        event,
+                        Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph)
+                    )
+                })
+                .map(|event| match event {
+                    Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+                    event => event,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Tab label rendered as inline Markdown, preceded by its icon.
+pub fn label(tab: &TabConfig) -> String {
+    let mut html = String::new();
+
+    if let Some(icon) = &tab.icon {
+        html.push_str(&format!(
+            "<i class=\"{}\"></i> ",
+            escape(&icon_classes(icon))
+        ));
+    }
+
+    match label_events(tab) {
+        Some(events) => push_html(&mut html, events.into_iter()),
+        None => html.push_str(&escape(&tab.name)),
+    }
+
+    html
+}
+
+/// Tab label with Markdown formatting removed.
+pub fn plain_label(tab: &TabConfig) -> String {
+    match label_events(tab) {
+        Some(events) => events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Text(text) | Event::Code(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect(),
+        None => tab.name.clone(),
+    }
+}
+
+/// Tab label as inline Markdown, with HTML escaped like in the rendered label.
+fn escaped_markdown_label(tab: &TabConfig) -> String {
+    let label = tab.markdown_label();
+    let mut escaped = String::new();
+    let mut offset = 0;
+
+    for (event, span) in Parser::new(label).into_offset_iter() {
+        if let Event::Html(_) | Event::InlineHtml(_) = event {
+            escaped.push_str(&label[offset..span.start]);
+            escaped.push_str(&escape(&label[span.clone()]));
+            offset = span.end;
+        }
+    }
+    escaped.push_str(&label[offset..]);
+
+    escaped
+}
+
+/// Render tabs as Markdown for renderers other than `html`.
+pub fn markdown(config: &TabsConfig, fallback: &FallbackConfig) -> String {
+    config
+        .tabs
+        .iter()
+        .map(|(tab, tab_content)| match fallback.format {
+            FallbackFormat::Headings => format!(
+                "{} {}\n\n{}\n",
+                "#".repeat(fallback.heading_level.clamp(1, 6) as usize),
+                escaped_markdown_label(tab),
+                tab_content.trim()
+            ),
+            FallbackFormat::DefinitionList => format!(
+                "{}\n:   {}\n",
+                escaped_markdown_label(tab),
                 tab_content
-            ))
-            .collect::<Vec<_>>()
-            .join("\n")
-    )
+                    .trim()
+                    .lines()
+                    .map(|line| match line.is_empty() {
+                        true => "".to_string(),
+                        false => format!("    {line}"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .trim_start()
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    fn tab(name: &str, icon: Option<&str>) -> TabConfig {
+        TabConfig {
+            name: name.to_string(),
+            icon: icon.map(|icon| icon.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!("<code>cargo</code>", label(&tab("`cargo`", None)));
+        assert_eq!("a &lt;b&gt; c", label(&tab("a <b> c", None)));
+        assert_eq!("1. Step", label(&tab("1. Step", None)));
+        assert_eq!(
+            "<i class=\"fa-brands fa-linux\"></i> <em>Linux</em>",
+            label(&tab("*Linux*", Some("brands linux")))
+        );
+        assert_eq!(
+            "<i class=\"fa-solid fa-terminal\"></i> Shell",
+            label(&tab("Shell", Some("terminal")))
+        );
+
+        assert_eq!("cargo", plain_label(&tab("`cargo`", None)));
+    }
+
+    #[test]
+    fn test_render_disabled() -> Result<()> {
+        let config = TabsConfig {
+            id: "mdbook-tabs-0".to_string(),
+            tabs: vec![
+                (
+                    TabConfig {
+                        disabled: Some(true),
+                        badge: Some("soon".to_string()),
+                        ..tab("Windows", None)
+                    },
+                    "Windows content.".to_string(),
+                ),
+                (tab("Linux", None), "Linux content.".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let html = HtmlTabsRenderer::default().render(&config, &PreprocessorConfig::default())?;
+        assert!(html.contains(
+            "<button class=\"mdbook-tab\" data-tabname=\"Windows\" disabled>Windows <span class=\"mdbook-tab-badge\">soon</span></button>"
+        ));
+        assert!(
+            html.contains(
+                "<button class=\"mdbook-tab active\" data-tabname=\"Linux\">Linux</button>"
+            )
+        );
+        assert!(html.contains("<div class=\"mdbook-tab-content hidden\" data-tabname=\"Windows\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_default() -> Result<()> {
+        let config = TabsConfig {
+            id: "mdbook-tabs-0".to_string(),
+            tabs: vec![
+                (tab("Linux", None), "Linux content.".to_string()),
+                (
+                    TabConfig {
+                        default: true,
+                        ..tab("Windows", None)
+                    },
+                    "Windows content.".to_string(),
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let html = HtmlTabsRenderer::default().render(&config, &PreprocessorConfig::default())?;
+        assert!(
+            html.contains("<button class=\"mdbook-tab\" data-tabname=\"Linux\">Linux</button>")
+        );
+        assert!(html.contains(
+            "<button class=\"mdbook-tab active\" data-tabname=\"Windows\">Windows</button>"
+        ));
+        assert!(html.contains("<div class=\"mdbook-tab-content hidden\" data-tabname=\"Linux\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let config = TabsConfig {
+            id: "mdbook-tabs-0".to_string(),
+            global: Some("os".to_string()),
+            tabs: vec![
+                (tab("Linux", None), "Linux content.".to_string()),
+                (tab("Windows", None), "Windows content.".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "<div class=\"mdbook-tabs-container\" id=\"mdbook-tabs-0\" data-tabglobal=\"os\">\n\
+            <nav class=\"mdbook-tabs\">\n\
+            <button class=\"mdbook-tab active\" data-tabname=\"Linux\">Linux</button>\n\
+            <button class=\"mdbook-tab\" data-tabname=\"Windows\">Windows</button>\n\
+            </nav>\n\
+            <div class=\"mdbook-tab-content\" data-tabname=\"Linux\" data-tablabel=\"Linux\">\n\n\
+            Linux content.\n\n\
+            </div>\n\
+            <div class=\"mdbook-tab-content hidden\" data-tabname=\"Windows\" data-tablabel=\"Windows\">\n\n\
+            Windows content.\n\n\
+            </div>\n\
+            </div>",
+            HtmlTabsRenderer::default().render(&config, &PreprocessorConfig::default())?
+        );
+
+        let preprocessor_config = PreprocessorConfig {
+            mode: Mode::Css,
+            class_prefix: Some("custom".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "<div class=\"custom-tabs-container custom-tabs-css\" id=\"mdbook-tabs-0\" data-tabglobal=\"os\">\n\
+            <input class=\"custom-tab-input\" type=\"radio\" name=\"mdbook-tabs-0\" id=\"mdbook-tabs-0-0\" data-tabname=\"Linux\" checked>\n\
+            <label class=\"custom-tab\" for=\"mdbook-tabs-0-0\" data-tabname=\"Linux\">Linux</label>\n\
+            <div class=\"custom-tab-content\" data-tabname=\"Linux\" data-tablabel=\"Linux\">\n\n\
+            Linux content.\n\n\
+            </div>\n\
+            <input class=\"custom-tab-input\" type=\"radio\" name=\"mdbook-tabs-0\" id=\"mdbook-tabs-0-1\" data-tabname=\"Windows\">\n\
+            <label class=\"custom-tab\" for=\"mdbook-tabs-0-1\" data-tabname=\"Windows\">Windows</label>\n\
+            <div class=\"custom-tab-content\" data-tabname=\"Windows\" data-tablabel=\"Windows\">\n\n\
+            Windows content.\n\n\
+            </div>\n\
+            </div>",
+            HtmlTabsRenderer::default().render(&config, &preprocessor_config)?
+        );
+
+        assert_eq!(
+            "Tabs group should have an `id`.",
+            HtmlTabsRenderer::default()
+                .render(
+                    &TabsConfig {
+                        id: String::new(),
+                        ..config
+                    },
+                    &preprocessor_config
+                )
+                .unwrap_err()
+                .to_string()
+        );
+
+        Ok(())
+    }
 }
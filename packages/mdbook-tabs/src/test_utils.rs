@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use mdbook_preprocessor::{
+    Preprocessor, PreprocessorContext,
+    book::{Book, BookItem, Chapter},
+};
+
+use crate::TabsPreprocessor;
+
+/// Context of a book in a directory, with the content of its `book.toml` and the name of the renderer.
+pub fn context(root: &Path, config: &str, renderer: &str) -> Result<PreprocessorContext> {
+    Ok(PreprocessorContext::new(
+        root.to_path_buf(),
+        config.parse()?,
+        renderer.to_string(),
+    ))
+}
+
+/// Chapter with content, at a path relative to the source directory.
+pub fn chapter(path: &str, content: &str) -> Chapter {
+    Chapter::new("A", content.to_string(), path, vec![])
+}
+
+/// Content of a chapter `a.md` after running the preprocessor.
+pub fn preprocess(ctx: &PreprocessorContext, content: &str) -> Result<String> {
+    let book = Book::new_with_items(vec![BookItem::Chapter(chapter("a.md", content))]);
+
+    match TabsPreprocessor::new().run(ctx, book)?.iter().next() {
+        Some(BookItem::Chapter(chapter)) => Ok(chapter.content.clone()),
+        _ => bail!("Chapter `a.md` not found."),
+    }
+}
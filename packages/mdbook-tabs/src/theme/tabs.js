@@ -1,3 +1,144 @@
+/**
+ * Prefix of the classes of tabs, which is replaced by `mdbook-tabs install` with the `class-prefix` option.
+ */
+const classPrefix = 'mdbook';
+
+/**
+ * Active tab name of each initialized container.
+ *
+ * @type {WeakMap<Element, string>}
+ */
+const activeTabs = new WeakMap();
+
+/**
+ * Name of the active tab of tabs.
+ *
+ * @param {Element} container
+ * @returns {string | undefined}
+ */
+const getActiveTab = (container) => {
+    const active = container.querySelector(
+        `:scope > .${classPrefix}-tabs > .${classPrefix}-tab.active, :scope > .${classPrefix}-tab-input:checked`
+    );
+    return active instanceof HTMLElement ? active.dataset.tabname : undefined;
+};
+
+/**
+ * Whether tabs have a tab with a name.
+ *
+ * @param {Element} container
+ * @param {string} name
+ * @returns {boolean}
+ */
+const hasTab = (container, name) =>
+    [...container.querySelectorAll(`:scope > .${classPrefix}-tab-content`)].some(
+        (content) => content instanceof HTMLElement && content.dataset.tabname === name
+    );
+
+/**
+ * Names of the tabs of tabs which are not disabled.
+ *
+ * @param {Element} container
+ * @returns {string[]}
+ */
+const getEnabledTabs = (container) =>
+    [
+        ...container.querySelectorAll(
+            `:scope > .${classPrefix}-tabs > .${classPrefix}-tab, :scope > .${classPrefix}-tab-input`
+        ),
+    ]
+        .filter((tab) => tab instanceof HTMLElement && !tab.hasAttribute('disabled'))
+        .map((tab) => tab.dataset.tabname);
+
+/**
+ * Whether tabs have a tab with a name which is not disabled.
+ *
+ * @param {Element} container
+ * @param {string} name
+ * @returns {boolean}
+ */
+const isTabEnabled = (container, name) => hasTab(container, name) && getEnabledTabs(container).includes(name);
+
+/**
+ * Group of tabs, i.e. their global or their ID.
+ *
+ * @param {HTMLElement} container
+ * @returns {string}
+ */
+const getGroup = (container) => container.dataset.tabglobal || container.id;
+
+/**
+ * Where the selected tab of tabs is stored, unless it is not remembered.
+ *
+ * @param {HTMLElement} container
+ * @returns {{storage: string, key: string} | undefined}
+ */
+const getStorageLocation = (container) => {
+    const storage = container.dataset.tabstorage || 'local';
+    const global = container.dataset.tabglobal;
+
+    if (storage === 'none' || (!global && container.dataset.tabremember === undefined)) {
+        return undefined;
+    }
+
+    if (storage === 'url') {
+        return {storage, key: `tab-${getGroup(container)}`};
+    }
+
+    // IDs of tabs are unique within the book, so tabs on the print page share their stored tab with the chapter page.
+    const namespace = container.dataset.tabnamespace ? `${container.dataset.tabnamespace}-` : '';
+    return {storage, key: `mdbook-tabs-${namespace}${getGroup(container)}`};
+};
+
+/**
+ * Load the stored tab name of tabs.
+ *
+ * @param {HTMLElement} container
+ * @returns {string | null}
+ */
+const loadTab = (container) => {
+    const storageLocation = getStorageLocation(container);
+    if (!storageLocation) {
+        return null;
+    }
+
+    switch (storageLocation.storage) {
+        case 'url':
+            return new URLSearchParams(window.location.search).get(storageLocation.key);
+        case 'session':
+            return sessionStorage.getItem(storageLocation.key);
+        default:
+            return localStorage.getItem(storageLocation.key);
+    }
+};
+
+/**
+ * Store the tab name of tabs.
+ *
+ * @param {HTMLElement} container
+ * @param {string} name
+ */
+const storeTab = (container, name) => {
+    const storageLocation = getStorageLocation(container);
+    if (!storageLocation) {
+        return;
+    }
+
+    switch (storageLocation.storage) {
+        case 'url': {
+            const url = new URL(window.location.href);
+            url.searchParams.set(storageLocation.key, name);
+            history.replaceState(history.state, '', url);
+            break;
+        }
+        case 'session':
+            sessionStorage.setItem(storageLocation.key, name);
+            break;
+        default:
+            localStorage.setItem(storageLocation.key, name);
+    }
+};
+
 /**
  * Change active tab of tabs.
  *
@@ -5,14 +146,25 @@
  * @param {string} name
  */
 const changeTab = (container, name) => {
+    if (!hasTab(container, name)) {
+        return;
+    }
+    if (!isTabEnabled(container, name)) {
+        // Disabled tabs can't be selected, so the first enabled tab is selected instead.
+        name = getEnabledTabs(container)[0];
+        if (name === undefined) {
+            return;
+        }
+    }
+
     for (const child of container.children) {
         if (!(child instanceof HTMLElement)) {
             continue;
         }
 
-        if (child.classList.contains('mdbook-tabs')) {
+        if (child.classList.contains(`${classPrefix}-tabs`)) {
             for (const tab of child.children) {
-                if (!(tab instanceof HTMLElement)) {
+                if (!(tab instanceof HTMLElement) || !tab.classList.contains(`${classPrefix}-tab`)) {
                     continue;
                 }
 
@@ -22,54 +174,326 @@ const changeTab = (container, name) => {
                     tab.classList.remove('active');
                 }
             }
-        } else if (child.classList.contains('mdbook-tab-content')) {
+        } else if (child.classList.contains(`${classPrefix}-tab-content`)) {
             if (child.dataset.tabname === name) {
                 child.classList.remove('hidden');
             } else {
                 child.classList.add('hidden');
             }
+        } else if (child instanceof HTMLInputElement && child.classList.contains(`${classPrefix}-tab-input`)) {
+            child.checked = child.dataset.tabname === name;
+        } else if (child instanceof HTMLSelectElement && child.classList.contains(`${classPrefix}-tabs-dropdown`)) {
+            child.value = name;
         }
     }
+
+    if (container instanceof HTMLElement && activeTabs.has(container) && activeTabs.get(container) !== name) {
+        activeTabs.set(container, name);
+
+        container.dispatchEvent(
+            new CustomEvent('mdbook-tabs:change', {
+                bubbles: true,
+                detail: {group: getGroup(container), key: name, container},
+            })
+        );
+    }
 };
 
-document.addEventListener('DOMContentLoaded', () => {
-    const tabs = document.querySelectorAll('.mdbook-tab');
+/**
+ * Change active tab of tabs and of all tabs with the same global, and remember it.
+ *
+ * @param {Element} container
+ * @param {string} name
+ */
+const selectTab = (container, name) => {
+    if (!(container instanceof HTMLElement)) {
+        return;
+    }
+
+    const global = container.dataset.tabglobal;
+
+    changeTab(container, name);
+    storeTab(container, name);
+
+    if (global) {
+        const globalContainers = document.querySelectorAll(
+            `.${classPrefix}-tabs-container[data-tabglobal="${CSS.escape(global)}"]`
+        );
+        for (const globalContainer of globalContainers) {
+            changeTab(globalContainer, name);
+        }
+    }
+};
+
+/**
+ * Add a dropdown to tabs, which replaces the tab buttons when the tabs are narrower than their dropdown width.
+ *
+ * @param {HTMLElement} container
+ */
+const createDropdown = (container) => {
+    const width = Number(container.dataset.tabdropdown);
+    const tabs = container.querySelectorAll(
+        `:scope > .${classPrefix}-tabs > .${classPrefix}-tab, :scope > .${classPrefix}-tab`
+    );
+
+    const select = document.createElement('select');
+    select.className = `${classPrefix}-tabs-dropdown`;
+    select.setAttribute('aria-label', 'Select tab');
+
+    for (const tab of tabs) {
+        if (!(tab instanceof HTMLElement)) {
+            continue;
+        }
+
+        const option = document.createElement('option');
+        option.value = tab.dataset.tabname;
+        option.textContent = tab.textContent;
+        option.disabled = !isTabEnabled(container, tab.dataset.tabname);
+        select.append(option);
+    }
+
+    const active = getActiveTab(container);
+    if (active) {
+        select.value = active;
+    }
+
+    select.addEventListener('change', () => selectTab(container, select.value));
+    container.prepend(select);
+
+    const observer = new ResizeObserver(() => {
+        container.classList.toggle('collapsed', container.clientWidth < width);
+    });
+    observer.observe(container);
+};
+
+/**
+ * Whether tab content is hidden.
+ *
+ * @param {Element} content
+ * @returns {boolean}
+ */
+const isTabHidden = (content) =>
+    content.classList.contains('hidden') || window.getComputedStyle(content).display === 'none';
+
+/**
+ * Activate the tabs containing an element, walking up nested tabs.
+ *
+ * @param {Element} element
+ * @returns {boolean} Whether a tab was activated.
+ */
+const revealTab = (element) => {
+    let changed = false;
+
+    let content = element.closest(`.${classPrefix}-tab-content`);
+    while (content instanceof HTMLElement && content.parentElement) {
+        const container = content.parentElement;
+
+        if (isTabHidden(content)) {
+            changeTab(container, content.dataset.tabname);
+            changed = true;
+        }
+
+        content = container.closest(`.${classPrefix}-tab-content`);
+    }
+
+    return changed;
+};
+
+/**
+ * Activate the tabs containing the target of the URL fragment.
+ */
+const revealHashTarget = () => {
+    if (!window.location.hash) {
+        return;
+    }
+
+    const target = document.getElementById(decodeURIComponent(window.location.hash.substring(1)));
+    if (target && revealTab(target)) {
+        target.scrollIntoView();
+    }
+};
+
+/**
+ * Activate the tabs containing search highlights, unless the tabs already show a highlight.
+ *
+ * @param {Iterable<Element>} marks
+ */
+const revealMarks = (marks) => {
+    for (const mark of marks) {
+        let content = mark.closest(`.${classPrefix}-tab-content`);
+        while (content && content.parentElement && !isTabHidden(content)) {
+            content = content.parentElement.closest(`.${classPrefix}-tab-content`);
+        }
+        if (!content || !content.parentElement) {
+            continue;
+        }
+
+        const hasVisibleMark = [...content.parentElement.children].some(
+            (child) =>
+                child.classList.contains(`${classPrefix}-tab-content`) &&
+                !isTabHidden(child) &&
+                child.querySelector('mark')
+        );
+        if (hasVisibleMark) {
+            continue;
+        }
+
+        revealTab(mark);
+    }
+};
+
+/**
+ * Initialize tabs, unless they are already initialized.
+ *
+ * @param {HTMLElement} container
+ */
+const initContainer = (container) => {
+    if (activeTabs.has(container)) {
+        return;
+    }
+    activeTabs.set(container, getActiveTab(container));
+
+    const tabs = container.querySelectorAll(`:scope > .${classPrefix}-tabs > .${classPrefix}-tab`);
     for (const tab of tabs) {
         tab.addEventListener('click', () => {
             if (!(tab instanceof HTMLElement)) {
                 return;
             }
 
-            if (!tab.parentElement || !tab.parentElement.parentElement) {
+            selectTab(container, tab.dataset.tabname);
+        });
+    }
+
+    const inputs = container.querySelectorAll(`:scope > .${classPrefix}-tab-input`);
+    for (const input of inputs) {
+        input.addEventListener('change', () => {
+            if (!(input instanceof HTMLInputElement) || !input.checked) {
                 return;
             }
 
-            const container = tab.parentElement.parentElement;
-            const name = tab.dataset.tabname;
-            const global = container.dataset.tabglobal;
+            selectTab(container, input.dataset.tabname);
+        });
+    }
+
+    const showAllButtons = container.querySelectorAll(
+        `:scope > .${classPrefix}-tabs > .${classPrefix}-tabs-show-all, :scope > .${classPrefix}-tabs-show-all`
+    );
+    for (const button of showAllButtons) {
+        button.addEventListener('click', () => {
+            const showAll = container.classList.toggle('show-all');
+
+            button.classList.toggle('active', showAll);
+            button.textContent = showAll ? 'Show one' : 'Show all';
+        });
+    }
 
-            changeTab(container, name);
+    if (container.dataset.tabdropdown) {
+        createDropdown(container);
+    }
 
-            if (global) {
-                localStorage.setItem(`mdbook-tabs-${global}`, name);
+    const name = loadTab(container);
+    if (name && isTabEnabled(container, name)) {
+        changeTab(container, name);
+    }
+};
+
+/**
+ * Initialize all tabs in an element, including the element itself.
+ *
+ * @param {Element} element
+ */
+const initContainers = (element) => {
+    if (element instanceof HTMLElement && element.classList.contains(`${classPrefix}-tabs-container`)) {
+        initContainer(element);
+    }
+
+    for (const container of element.querySelectorAll(`.${classPrefix}-tabs-container`)) {
+        if (container instanceof HTMLElement) {
+            initContainer(container);
+        }
+    }
+};
 
-                const globalContainers = document.querySelectorAll(
-                    `.mdbook-tabs-container[data-tabglobal="${global}"]`
-                );
-                for (const globalContainer of globalContainers) {
-                    changeTab(globalContainer, name);
+window.mdbookTabs = {
+    /**
+     * Select a tab in a group, i.e. in all tabs with a global or in the tabs with an ID.
+     *
+     * @param {string} group
+     * @param {string} key
+     * @returns {boolean} Whether a tab was selected.
+     */
+    select: (group, key) => {
+        const containers = [
+            ...document.querySelectorAll(`.${classPrefix}-tabs-container[data-tabglobal="${CSS.escape(group)}"]`),
+        ];
+        if (containers.length === 0) {
+            const container = document.getElementById(group);
+            if (container && container.classList.contains(`${classPrefix}-tabs-container`)) {
+                containers.push(container);
+            }
+        }
+
+        const container = containers.find((container) => isTabEnabled(container, key));
+        if (!container) {
+            return false;
+        }
+
+        selectTab(container, key);
+        return true;
+    },
+};
+
+document.addEventListener('DOMContentLoaded', () => {
+    initContainers(document.body);
+
+    // Tabs can be added by other scripts after the page has loaded.
+    const containerObserver = new MutationObserver((mutations) => {
+        for (const mutation of mutations) {
+            for (const node of mutation.addedNodes) {
+                if (node instanceof Element) {
+                    initContainers(node);
                 }
             }
-        });
-    }
+        }
+    });
+    containerObserver.observe(document.body, {childList: true, subtree: true});
+
+    revealHashTarget();
+    window.addEventListener('hashchange', revealHashTarget);
+
+    // Links to the current URL fragment don't trigger a hash change, so reveal their target on click.
+    document.addEventListener('click', (event) => {
+        if (!(event.target instanceof Element)) {
+            return;
+        }
 
-    const containers = document.querySelectorAll('.mdbook-tabs-container[data-tabglobal]');
-    for (const container of containers) {
-        const global = container.dataset.tabglobal;
+        const link = event.target.closest('a[href*="#"]');
+        if (!(link instanceof HTMLAnchorElement) || link.pathname !== window.location.pathname || !link.hash) {
+            return;
+        }
 
-        const name = localStorage.getItem(`mdbook-tabs-${global}`);
-        if (name && document.querySelector(`.mdbook-tab[data-tabname="${name}"]`)) {
-            changeTab(container, name);
+        const target = document.getElementById(decodeURIComponent(link.hash.substring(1)));
+        if (target) {
+            revealTab(target);
         }
+    });
+
+    // mdBook's search highlights matches after the page has loaded.
+    if (new URLSearchParams(window.location.search).has('highlight')) {
+        revealMarks(document.querySelectorAll('mark'));
+
+        const observer = new MutationObserver((mutations) => {
+            const marks = [];
+            for (const mutation of mutations) {
+                for (const node of mutation.addedNodes) {
+                    if (node instanceof Element && node.tagName === 'MARK') {
+                        marks.push(node);
+                    }
+                }
+            }
+
+            revealMarks(marks);
+        });
+        observer.observe(document.body, {childList: true, subtree: true});
     }
 });
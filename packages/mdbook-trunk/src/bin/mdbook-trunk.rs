@@ -21,6 +21,10 @@ use semver::{Version, VersionReq};
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Rebuild all packages, even if their cached build is unchanged.
+    #[arg(long)]
+    force: bool,
 }
 
 #[derive(Subcommand)]
@@ -44,7 +48,7 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
     let preprocessor = TrunkPreprocessor::new();
-    let renderer = TrunkRenderer::new();
+    let renderer = TrunkRenderer::new().force(cli.force);
 
     match &cli.command {
         Some(subcommand) => match subcommand {
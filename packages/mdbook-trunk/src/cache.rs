@@ -0,0 +1,302 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use cargo::{
+    core::Workspace,
+    util::{StableHasher, to_hex},
+};
+use fs_extra::dir::{CopyOptions, copy};
+
+use crate::config::{BuildConfig, UrlBase};
+
+/// Directories which are not sources of a package.
+const IGNORED_DIRS: [&str; 3] = ["dist", "node_modules", "target"];
+
+/// Cache of Trunk build outputs, which are reused while the fingerprint of their inputs is unchanged.
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Directory of the cached output of a build.
+    pub fn output_dir(&self, build_config: &BuildConfig) -> PathBuf {
+        self.dir.join(build_config.dest_name())
+    }
+
+    /// Cargo target directory of a build.
+    ///
+    /// Each build has its own target directory, so concurrent builds of a package with different features don't
+    /// overwrite each other's artifacts.
+    pub fn target_dir(&self, build_config: &BuildConfig) -> PathBuf {
+        self.dir.join("target").join(build_config.dest_name())
+    }
+
+    fn fingerprint_path(&self, build_config: &BuildConfig) -> PathBuf {
+        self.dir
+            .join(format!("{}.fingerprint", build_config.dest_name()))
+    }
+
+    /// Whether the cached output of a build matches a fingerprint.
+    pub fn is_fresh(&self, build_config: &BuildConfig, fingerprint: &str) -> bool {
+        self.output_dir(build_config).exists()
+            && fs::read_to_string(self.fingerprint_path(build_config))
+                .is_ok_and(|cached| cached == fingerprint)
+    }
+
+    /// Remove the fingerprint of a build, e.g. before rebuilding it.
+    pub fn invalidate(&self, build_config: &BuildConfig) -> Result<()> {
+        let path = self.fingerprint_path(build_config);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Store the fingerprint of a finished build.
+    pub fn store(&self, build_config: &BuildConfig, fingerprint: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.fingerprint_path(build_config), fingerprint)?;
+
+        Ok(())
+    }
+
+    /// Copy the cached output of a build to a directory.
+    pub fn copy_output(&self, build_config: &BuildConfig, dest_dir: &Path) -> Result<()> {
+        if dest_dir.exists() {
+            fs::remove_dir_all(dest_dir)?;
+        }
+        fs::create_dir_all(dest_dir)?;
+
+        copy(
+            self.output_dir(build_config),
+            dest_dir,
+            &CopyOptions::new().content_only(true),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Fingerprint of the inputs of a build.
+///
+/// This includes the sources of the package and its path dependencies in the workspace (including `index.html` and
+/// `Trunk.toml`), `Cargo.lock`, the features, the public URL and the version of this plugin.
+pub fn fingerprint(
+    workspace: &Workspace,
+    build_config: &BuildConfig,
+    url_base: &UrlBase,
+) -> Result<String> {
+    let mut hasher = StableHasher::new();
+
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    build_config.hash(&mut hasher);
+    url_base.public_url(build_config).hash(&mut hasher);
+
+    let lock_file = workspace.root().join("Cargo.lock");
+    if lock_file.exists() {
+        fs::read(lock_file)?.hash(&mut hasher);
+    }
+
+    for dir in source_dirs(workspace, &build_config.package) {
+        hash_dir(&dir, &dir, &mut hasher)?;
+    }
+
+    Ok(to_hex(Hasher::finish(&hasher)))
+}
+
+/// Root directories of a workspace package and its path dependencies in the workspace.
+fn source_dirs(workspace: &Workspace, package: &str) -> BTreeSet<PathBuf> {
+    let mut dirs = BTreeSet::new();
+    let mut queue = vec![package.to_string()];
+
+    while let Some(name) = queue.pop() {
+        let Some(package) = workspace
+            .members()
+            .find(|member| member.name().as_str() == name)
+        else {
+            continue;
+        };
+
+        if !dirs.insert(package.root().to_path_buf()) {
+            continue;
+        }
+
+        for dependency in package.dependencies() {
+            if dependency.source_id().is_path() {
+                queue.push(dependency.package_name().to_string());
+            }
+        }
+    }
+
+    dirs
+}
+
+/// Hash the paths and contents of the files in a directory, skipping hidden and build directories.
+fn hash_dir(root: &Path, dir: &Path, hasher: &mut StableHasher) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_ref()) {
+                hash_dir(root, &path, hasher)?;
+            }
+        } else {
+            path.strip_prefix(root)?.hash(hasher);
+            fs::read(&path)?.hash(hasher);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use cargo::GlobalContext;
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::build_config;
+
+    /// Workspace with an `app` package, which depends on a `lib` package.
+    fn write_workspace(root: &Path) -> Result<()> {
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\", \"lib\"]\nresolver = \"2\"\n",
+        )?;
+
+        fs::create_dir_all(root.join("app/src"))?;
+        fs::write(
+            root.join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n\
+            [dependencies]\nlib = { path = \"../lib\" }\n\n\
+            [features]\nbutton = []\n",
+        )?;
+        fs::write(root.join("app/index.html"), "<html></html>")?;
+        fs::write(root.join("app/src/main.rs"), "fn main() {}")?;
+
+        fs::create_dir_all(root.join("lib/src"))?;
+        fs::write(
+            root.join("lib/Cargo.toml"),
+            "[package]\nname = \"lib\"\nversion = \"0.1.0\"\nedition = \"2024\"\n",
+        )?;
+        fs::write(root.join("lib/src/lib.rs"), "")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let root = root.path();
+        write_workspace(root)?;
+
+        let gctx = GlobalContext::default()?;
+        let workspace = Workspace::new(&root.join("Cargo.toml"), &gctx)?;
+        let url_base = UrlBase::Absolute("/".to_string());
+
+        let fingerprint = || fingerprint(&workspace, &build_config(&[]), &url_base);
+        let initial = fingerprint()?;
+        assert_eq!(initial, fingerprint()?);
+
+        // Build outputs and hidden files are not inputs.
+        for dir in ["app/dist", "app/target", "app/node_modules", "app/.git"] {
+            fs::create_dir_all(root.join(dir))?;
+            fs::write(root.join(dir).join("file"), "")?;
+        }
+        assert_eq!(initial, fingerprint()?);
+
+        // Sources of the package and its path dependencies are inputs.
+        fs::write(root.join("app/src/main.rs"), "fn main() { todo!() }")?;
+        assert_ne!(initial, fingerprint()?);
+        fs::write(root.join("app/src/main.rs"), "fn main() {}")?;
+        assert_eq!(initial, fingerprint()?);
+
+        fs::write(root.join("lib/src/lib.rs"), "pub fn lib() {}")?;
+        let changed = fingerprint()?;
+        assert_ne!(initial, changed);
+
+        fs::write(root.join("app/index.html"), "<html><body></body></html>")?;
+        assert_ne!(changed, fingerprint()?);
+        let changed = fingerprint()?;
+
+        fs::write(root.join("Cargo.lock"), "version = 4\n")?;
+        assert_ne!(changed, fingerprint()?);
+        let changed = fingerprint()?;
+
+        // Features and the public URL are inputs.
+        assert_ne!(
+            changed,
+            super::fingerprint(&workspace, &build_config(&["button"]), &url_base)?
+        );
+        assert_ne!(
+            changed,
+            super::fingerprint(
+                &workspace,
+                &build_config(&[]),
+                &UrlBase::Absolute("/book/".to_string())
+            )?
+        );
+        assert_ne!(
+            changed,
+            super::fingerprint(&workspace, &build_config(&[]), &UrlBase::Relative)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_cache() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let cache = BuildCache::new(root.path().join("cache"));
+        let button = build_config(&["button"]);
+
+        assert!(!cache.is_fresh(&button, "a"));
+
+        fs::create_dir_all(cache.output_dir(&button))?;
+        fs::write(cache.output_dir(&button).join("index.html"), "a")?;
+        cache.store(&button, "a")?;
+
+        assert!(cache.is_fresh(&button, "a"));
+        assert!(!cache.is_fresh(&button, "b"));
+        assert!(!cache.is_fresh(&build_config(&[]), "a"));
+
+        // Outputs replace the existing destination.
+        let dest_dir = root.path().join("book/app--button");
+        fs::create_dir_all(&dest_dir)?;
+        fs::write(dest_dir.join("stale.js"), "")?;
+        cache.copy_output(&button, &dest_dir)?;
+        assert_eq!("a", fs::read_to_string(dest_dir.join("index.html"))?);
+        assert!(!dest_dir.join("stale.js").exists());
+
+        cache.invalidate(&button)?;
+        assert!(!cache.is_fresh(&button, "a"));
+
+        // Builds of a package with different features have their own target directory.
+        assert_ne!(
+            cache.target_dir(&button),
+            cache.target_dir(&build_config(&[]))
+        );
+        assert!(
+            !cache
+                .target_dir(&button)
+                .starts_with(cache.output_dir(&button))
+        );
+
+        Ok(())
+    }
+}
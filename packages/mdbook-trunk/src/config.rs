@@ -1,9 +1,87 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    num::NonZero,
+    path::{Path, PathBuf},
+    thread,
+};
 
 use anyhow::{Result, anyhow};
 use cargo::{core::Workspace, ops::Packages};
+use mdbook_core::{config::Config as BookConfig, utils::fs::path_to_root};
 use serde::{Deserialize, Serialize};
 
+/// Configuration of the renderer in `[output.trunk]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RendererConfig {
+    pub jobs: Option<usize>,
+    pub cache_dir: Option<PathBuf>,
+    pub force: bool,
+    pub relative_urls: bool,
+    pub html_output: bool,
+    pub output_dir: Option<PathBuf>,
+}
+
+impl RendererConfig {
+    /// Number of concurrent Trunk builds, which defaults to the available parallelism.
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZero::get))
+            .max(1)
+    }
+}
+
+/// Base of the URLs of builds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UrlBase {
+    /// URL of the book root, from `[output.html] site-url`.
+    Absolute(String),
+    /// Relative to the chapter, for books served from an unknown path or `file://`.
+    Relative,
+}
+
+impl UrlBase {
+    /// URL base of the book, from `[output.trunk] relative-urls` and `[output.html] site-url`.
+    pub fn from_config(config: &BookConfig) -> Result<Self> {
+        let renderer_config = config
+            .get::<RendererConfig>("output.trunk")?
+            .unwrap_or_default();
+        if renderer_config.relative_urls {
+            return Ok(Self::Relative);
+        }
+
+        let site_url = config
+            .html_config()
+            .and_then(|html_config| html_config.site_url)
+            .unwrap_or_default();
+        let site_url = site_url.trim_end_matches('/');
+
+        Ok(Self::Absolute(if site_url.contains("://") {
+            format!("{site_url}/")
+        } else {
+            format!("/{}/", site_url.trim_start_matches('/')).replace("//", "/")
+        }))
+    }
+
+    /// URL of the `index.html` of a build in a chapter.
+    pub fn index_url(&self, build_config: &BuildConfig, chapter_path: Option<&Path>) -> String {
+        let root = match self {
+            Self::Absolute(root) => root.clone(),
+            Self::Relative => chapter_path.map(path_to_root).unwrap_or_default(),
+        };
+
+        format!("{root}{}/index.html", build_config.dest_name())
+    }
+
+    /// Public URL of a build, from which Trunk serves its assets.
+    pub fn public_url(&self, build_config: &BuildConfig) -> String {
+        match self {
+            Self::Absolute(root) => format!("{root}{}/", build_config.dest_name()),
+            Self::Relative => "./".to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FileReplacement {
     pub find: String,
@@ -65,3 +143,72 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+    use crate::test_utils::build_config;
+
+    #[test]
+    fn test_url_base_from_config() -> Result<()> {
+        for (content, expected) in [
+            ("", UrlBase::Absolute("/".to_string())),
+            (
+                "[output.html]\nsite-url = \"my-book\"\n",
+                UrlBase::Absolute("/my-book/".to_string()),
+            ),
+            (
+                "[output.html]\nsite-url = \"/my-book/\"\n",
+                UrlBase::Absolute("/my-book/".to_string()),
+            ),
+            (
+                "[output.html]\nsite-url = \"https://example.com/my-book\"\n",
+                UrlBase::Absolute("https://example.com/my-book/".to_string()),
+            ),
+            (
+                "[output.html]\nsite-url = \"/my-book/\"\n\n[output.trunk]\nrelative-urls = true\n",
+                UrlBase::Relative,
+            ),
+        ] {
+            assert_eq!(
+                expected,
+                UrlBase::from_config(&content.parse()?)?,
+                "{content}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_url_base_urls() {
+        let button = build_config(&["button"]);
+
+        let absolute = UrlBase::Absolute("/my-book/".to_string());
+        assert_eq!("/my-book/app--button/", absolute.public_url(&button));
+        for chapter_path in [
+            None,
+            Some(Path::new("index.md")),
+            Some(Path::new("a/b/c.md")),
+        ] {
+            assert_eq!(
+                "/my-book/app--button/index.html",
+                absolute.index_url(&button, chapter_path)
+            );
+        }
+
+        let relative = UrlBase::Relative;
+        assert_eq!("./", relative.public_url(&button));
+        assert_eq!("app--button/index.html", relative.index_url(&button, None));
+        assert_eq!(
+            "app--button/index.html",
+            relative.index_url(&button, Some(Path::new("index.md")))
+        );
+        assert_eq!(
+            "../../app--button/index.html",
+            relative.index_url(&button, Some(Path::new("a/b/c.md")))
+        );
+    }
+}
@@ -1,7 +1,10 @@
+mod cache;
 mod config;
 mod parser;
 mod preprocessor;
 mod renderer;
+#[cfg(test)]
+mod test_utils;
 mod trunk;
 
 pub use preprocessor::TrunkPreprocessor;
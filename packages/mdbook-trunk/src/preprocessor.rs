@@ -7,7 +7,7 @@ use mdbook_preprocessor::{
     book::{Book, BookItem},
 };
 
-use crate::{parser::definition::parse_definitions, trunk::trunk};
+use crate::{config::UrlBase, parser::definition::parse_definitions, trunk::trunk};
 
 pub struct TrunkPreprocessor;
 
@@ -28,13 +28,14 @@ impl Preprocessor for TrunkPreprocessor {
         "trunk"
     }
 
-    fn run(&self, _ctx: &PreprocessorContext, book: Book) -> Result<Book> {
+    fn run(&self, ctx: &PreprocessorContext, book: Book) -> Result<Book> {
         let mut book = book.clone();
 
         let gctx = GlobalContext::default()?;
         let workspace = Workspace::new(&find_root_manifest_for_wd(&env::current_dir()?)?, &gctx)?;
+        let url_base = UrlBase::from_config(&ctx.config)?;
 
-        process_items(&workspace, &mut book.items)?;
+        process_items(&workspace, &url_base, &mut book.items)?;
 
         Ok(book)
     }
@@ -44,7 +45,11 @@ impl Preprocessor for TrunkPreprocessor {
     }
 }
 
-fn process_items(workspace: &Workspace, items: &mut Vec<BookItem>) -> Result<()> {
+fn process_items(
+    workspace: &Workspace,
+    url_base: &UrlBase,
+    items: &mut Vec<BookItem>,
+) -> Result<()> {
     for section in items {
         if let BookItem::Chapter(chapter) = section {
             let blocks = parse_definitions(chapter)?;
@@ -52,7 +57,7 @@ fn process_items(workspace: &Workspace, items: &mut Vec<BookItem>) -> Result<()>
             let mut offset: usize = 0;
 
             for (span, config) in blocks {
-                let replacement = trunk(workspace, &config)?;
+                let replacement = trunk(workspace, &config, url_base, chapter.path.as_deref())?;
 
                 chapter
                     .content
@@ -61,7 +66,7 @@ fn process_items(workspace: &Workspace, items: &mut Vec<BookItem>) -> Result<()>
                 offset += replacement.len() - span.len();
             }
 
-            process_items(workspace, &mut chapter.sub_items)?;
+            process_items(workspace, url_base, &mut chapter.sub_items)?;
         }
     }
 
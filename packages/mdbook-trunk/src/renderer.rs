@@ -1,16 +1,36 @@
-use std::{collections::HashSet, env};
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use cargo::{GlobalContext, core::Workspace, util::important_paths::find_root_manifest_for_wd};
+use log::info;
 use mdbook_renderer::{RenderContext, Renderer, book::BookItem};
 
-use crate::{config::BuildConfig, parser::iframe::parse_iframes, trunk::build};
+use crate::{
+    cache::{BuildCache, fingerprint},
+    config::{BuildConfig, RendererConfig, UrlBase},
+    parser::iframe::parse_iframes,
+    trunk::build,
+};
 
-pub struct TrunkRenderer;
+pub struct TrunkRenderer {
+    force: bool,
+}
 
 impl TrunkRenderer {
     pub fn new() -> Self {
-        Self
+        Self { force: false }
+    }
+
+    /// Rebuild all packages, even if their cached build is unchanged.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
     }
 }
 
@@ -26,29 +46,153 @@ impl Renderer for TrunkRenderer {
     }
 
     fn render(&self, ctx: &RenderContext) -> Result<()> {
+        let config = ctx
+            .config
+            .get::<RendererConfig>("output.trunk")?
+            .unwrap_or_default();
+
         let gctx = GlobalContext::default()?;
         let workspace = Workspace::new(&find_root_manifest_for_wd(&env::current_dir()?)?, &gctx)?;
 
-        let builds = process_items(&ctx.book.items)?;
+        let cache = BuildCache::new(match &config.cache_dir {
+            Some(cache_dir) => ctx.root.join(cache_dir),
+            None => workspace
+                .target_dir()
+                .into_path_unlocked()
+                .join("mdbook-trunk"),
+        });
+        let force = self.force || config.force;
+        let url_base = UrlBase::from_config(&ctx.config)?;
+        let output_dir = output_dir(ctx, &config)?;
+
+        let mut builds = process_items(&ctx.book.items)?
+            .into_iter()
+            .map(|build_config| {
+                let package_root = build_config.package_root(&workspace)?;
+                let dest_dir = output_dir.join(build_config.dest_name());
+                let fingerprint = fingerprint(&workspace, &build_config, &url_base)?;
+
+                Ok((build_config, package_root, dest_dir, fingerprint))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        builds.sort_by_key(|(build_config, _, _, _)| build_config.dest_name());
 
-        // let mut handles = vec![];
-        for build_config in builds {
-            let package_root = build_config.package_root(&workspace)?;
-            let dest_dir = ctx.destination.join(build_config.dest_name());
+        let jobs = config.jobs().min(builds.len());
+        info!(
+            "Building {} package(s) with {jobs} job(s) into `{}`.",
+            builds.len(),
+            output_dir.display()
+        );
 
-            // handles.push(thread::spawn(move || {
-            //     build(build_config, &package_root, &dest_dir)
-            // }));
+        run_builds(
+            jobs,
+            builds,
+            |(build_config, package_root, dest_dir, fingerprint)| {
+                build_cached(
+                    &cache,
+                    force,
+                    &url_base,
+                    &build_config,
+                    &package_root,
+                    &dest_dir,
+                    &fingerprint,
+                )
+            },
+        )
+    }
+}
+
+/// Run builds on a number of threads, reporting all failed builds together.
+fn run_builds<T: Send>(
+    jobs: usize,
+    builds: Vec<T>,
+    build: impl Fn(T) -> Result<()> + Sync,
+) -> Result<()> {
+    let queue = Mutex::new(builds.into_iter());
+    let errors = Mutex::new(vec![]);
 
-            build(build_config, &package_root, &dest_dir)?;
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let Some(item) = queue.lock().expect("Lock should not be poisoned.").next()
+                    else {
+                        break;
+                    };
+
+                    if let Err(err) = build(item) {
+                        errors
+                            .lock()
+                            .expect("Lock should not be poisoned.")
+                            .push(format!("{err:#}"));
+                    }
+                }
+            });
         }
+    });
 
-        // for handle in handles {
-        //     handle.join().map_err(|err| anyhow!("{:?}", err))??;
-        // }
+    let mut errors = errors.into_inner().expect("Lock should not be poisoned.");
+    if !errors.is_empty() {
+        errors.sort();
+        bail!(
+            "{} Trunk build(s) failed:\n{}",
+            errors.len(),
+            errors.join("\n")
+        );
+    }
 
-        Ok(())
+    Ok(())
+}
+
+/// Directory to write builds to.
+///
+/// mdBook runs renderers in alphabetical order, so `trunk` runs after `html`. The HTML renderer removes the contents of
+/// its destination, so builds are written there after it ran.
+fn output_dir(ctx: &RenderContext, config: &RendererConfig) -> Result<PathBuf> {
+    if let Some(output_dir) = &config.output_dir {
+        return Ok(ctx.root.join(output_dir));
+    }
+
+    if config.html_output {
+        if !ctx.config.contains_key("output.html") {
+            bail!("Option `html-output` requires the `html` renderer.");
+        }
+
+        // There are multiple renderers, so each renderer has its own directory in the build directory.
+        return Ok(ctx.root.join(&ctx.config.build.build_dir).join("html"));
     }
+
+    Ok(ctx.destination.clone())
+}
+
+/// Build a package, unless its cached build is unchanged, and copy the build to its destination.
+fn build_cached(
+    cache: &BuildCache,
+    force: bool,
+    url_base: &UrlBase,
+    build_config: &BuildConfig,
+    package_root: &Path,
+    dest_dir: &Path,
+    fingerprint: &str,
+) -> Result<()> {
+    if !force && cache.is_fresh(build_config, fingerprint) {
+        info!(
+            "[{}] Unchanged, reusing cached build.",
+            build_config.dest_name()
+        );
+    } else {
+        cache.invalidate(build_config)?;
+        build(
+            build_config,
+            url_base,
+            package_root,
+            &cache.output_dir(build_config),
+            &cache.target_dir(build_config),
+        )?;
+        cache.store(build_config, fingerprint)?;
+    }
+
+    cache.copy_output(build_config, dest_dir)
 }
 
 fn process_items(items: &Vec<BookItem>) -> Result<HashSet<BuildConfig>> {
@@ -67,3 +211,114 @@ fn process_items(items: &Vec<BookItem>) -> Result<HashSet<BuildConfig>> {
 
     Ok(builds)
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+    use mdbook_renderer::book::Book;
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_run_builds() -> Result<()> {
+        let count = AtomicUsize::new(0);
+        run_builds(2, vec!["a", "b", "c"], |_| {
+            count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })?;
+        assert_eq!(3, count.load(Ordering::SeqCst));
+
+        // Failed builds don't stop other builds and are reported together.
+        let count = AtomicUsize::new(0);
+        let err = run_builds(2, vec!["a", "b", "c", "d"], |package| {
+            count.fetch_add(1, Ordering::SeqCst);
+            match package {
+                "b" | "d" => Err(anyhow!("Build of `{package}` failed.")),
+                _ => Ok(()),
+            }
+        })
+        .expect_err("Builds should fail.");
+
+        assert_eq!(4, count.load(Ordering::SeqCst));
+        assert_eq!(
+            "2 Trunk build(s) failed:\nBuild of `b` failed.\nBuild of `d` failed.",
+            err.to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_dir() -> Result<()> {
+        let ctx = |config: &str| -> Result<RenderContext> {
+            Ok(RenderContext::new(
+                "/root",
+                Book::new(),
+                config.parse()?,
+                "/root/book/trunk",
+            ))
+        };
+        let html = ctx("[output.html]\n\n[output.trunk]\n")?;
+
+        assert_eq!(
+            PathBuf::from("/root/book/trunk"),
+            output_dir(&html, &RendererConfig::default())?
+        );
+        assert_eq!(
+            PathBuf::from("/root/book/html"),
+            output_dir(
+                &html,
+                &RendererConfig {
+                    html_output: true,
+                    ..Default::default()
+                }
+            )?
+        );
+
+        // The output directory takes precedence over the HTML output.
+        assert_eq!(
+            PathBuf::from("/root/public"),
+            output_dir(
+                &html,
+                &RendererConfig {
+                    html_output: true,
+                    output_dir: Some(PathBuf::from("public")),
+                    ..Default::default()
+                }
+            )?
+        );
+
+        assert_eq!(
+            "Option `html-output` requires the `html` renderer.",
+            output_dir(
+                &ctx("[output.trunk]\n")?,
+                &RendererConfig {
+                    html_output: true,
+                    ..Default::default()
+                }
+            )
+            .unwrap_err()
+            .to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_jobs() {
+        let jobs = |jobs| {
+            RendererConfig {
+                jobs,
+                ..Default::default()
+            }
+            .jobs()
+        };
+
+        assert_eq!(3, jobs(Some(3)));
+        assert_eq!(1, jobs(Some(0)));
+        assert!(jobs(None) >= 1);
+    }
+}
@@ -0,0 +1,9 @@
+use crate::config::BuildConfig;
+
+/// Build config of the `app` package with features.
+pub fn build_config(features: &[&str]) -> BuildConfig {
+    BuildConfig {
+        package: "app".to_string(),
+        features: features.iter().map(|feature| feature.to_string()).collect(),
+    }
+}
@@ -32,7 +32,30 @@ const changeTrunkFile = (container, name) => {
     }
 };
 
+/**
+ * Update relative URLs of iframes, which are relative to their chapter, for the current page (e.g. the print page).
+ */
+const updateTrunkIframes = () => {
+    if (typeof path_to_root === 'undefined') {
+        return;
+    }
+
+    const iframes = document.querySelectorAll('.mdbook-trunk-iframe[data-mdbook-trunk-src]');
+    for (const iframe of iframes) {
+        if (!(iframe instanceof HTMLIFrameElement)) {
+            continue;
+        }
+
+        const src = `${path_to_root}${iframe.dataset.mdbookTrunkSrc}`;
+        if (iframe.getAttribute('src') !== src) {
+            iframe.setAttribute('src', src);
+        }
+    }
+};
+
 document.addEventListener('DOMContentLoaded', () => {
+    updateTrunkIframes();
+
     const files = document.querySelectorAll('.mdbook-trunk-file');
     for (const file of files) {
         file.addEventListener('click', () => {
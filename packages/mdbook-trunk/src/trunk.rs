@@ -1,26 +1,51 @@
-use std::{fs, path::Path, process::Command, str};
-
-use anyhow::{Result, bail};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, Stdio},
+    str, thread,
+};
+
+use anyhow::{Context, Result, bail};
 use cargo::core::Workspace;
 use htmlentity::entity::{CharacterSet, EncodeType, ICodedDataTrait, encode};
-use log::{error, info};
+use log::info;
 
-use crate::config::{BuildConfig, Config};
+use crate::config::{BuildConfig, Config, UrlBase};
 
-pub fn trunk(workspace: &Workspace, config: &Config) -> Result<String> {
+pub fn trunk(
+    workspace: &Workspace,
+    config: &Config,
+    url_base: &UrlBase,
+    chapter_path: Option<&Path>,
+) -> Result<String> {
     Ok(format!(
         "{}\n\n{}",
-        iframe(config)?,
+        iframe(config, url_base, chapter_path)?,
         files(workspace, config)?
     ))
 }
 
-pub fn iframe(config: &Config) -> Result<String> {
+pub fn iframe(config: &Config, url_base: &UrlBase, chapter_path: Option<&Path>) -> Result<String> {
+    let url = format!(
+        "{}{}",
+        config
+            .url_query
+            .as_ref()
+            .map(|query| format!("?{}", query.trim_start_matches('?')))
+            .unwrap_or("".into()),
+        config
+            .url_fragment
+            .as_ref()
+            .map(|fragment| format!("#{}", fragment.trim_start_matches('#')))
+            .unwrap_or("".into()),
+    );
+
     Ok(format!(
         "<iframe \
         data-mdbook-trunk=\"{}\" \
         class=\"mdbook-trunk-iframe\" \
-        src=\"/{}/index.html{}{}\" \
+        src=\"{}{}\"{} \
         {}></iframe>",
         encode(
             serde_json::to_string(config)?.as_bytes(),
@@ -28,17 +53,16 @@ pub fn iframe(config: &Config) -> Result<String> {
             &CharacterSet::SpecialChars
         )
         .to_string()?,
-        config.build_config().dest_name(),
-        config
-            .url_query
-            .as_ref()
-            .map(|query| format!("?{}", query.trim_start_matches('?')))
-            .unwrap_or("".into()),
-        config
-            .url_fragment
-            .as_ref()
-            .map(|fragment| format!("#{}", fragment.trim_start_matches('#')))
-            .unwrap_or("".into()),
+        url_base.index_url(&config.build_config(), chapter_path),
+        url,
+        match url_base {
+            // The print page is in the book root, so `trunk.js` updates relative URLs using `path_to_root`.
+            UrlBase::Relative => format!(
+                " data-mdbook-trunk-src=\"{}{url}\"",
+                url_base.index_url(&config.build_config(), None)
+            ),
+            UrlBase::Absolute(_) => "".into(),
+        },
         config
             .attributes
             .as_ref()
@@ -114,33 +138,104 @@ fn files(workspace: &Workspace, config: &Config) -> Result<String> {
     ))
 }
 
-pub fn build(config: BuildConfig, package_root: &Path, dest_dir: &Path) -> Result<()> {
+pub fn build(
+    config: &BuildConfig,
+    url_base: &UrlBase,
+    package_root: &Path,
+    dest_dir: &Path,
+    target_dir: &Path,
+) -> Result<()> {
+    let dest_name = config.dest_name();
+
     info!(
-        "Building `{}` with feature(s) `{}` using Trunk.",
+        "[{dest_name}] Building `{}` with feature(s) `{}` using Trunk.",
         config.package,
         config.features.join(", ")
     );
 
-    let output = Command::new("trunk")
+    let mut child = Command::new("trunk")
         .arg("build")
         .arg("--dist")
         .arg(dest_dir)
         .arg("--public-url")
-        .arg(format!("/{}/", config.dest_name()))
+        .arg(url_base.public_url(config))
         .arg("--no-default-features")
         .arg("--features")
         .arg(config.features.join(","))
         .current_dir(package_root)
-        .output()?;
-
-    if !output.status.success() {
-        error!(
-            "{}{}",
-            str::from_utf8(&output.stdout)?,
-            str::from_utf8(&output.stderr)?
+        .env("CARGO_TARGET_DIR", target_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("[{dest_name}] Failed to run Trunk."))?;
+
+    let stdout = child.stdout.take().expect("Trunk stdout should be piped.");
+    let stderr = child.stderr.take().expect("Trunk stderr should be piped.");
+
+    thread::scope(|scope| {
+        scope.spawn(|| log_output(&dest_name, stdout));
+        scope.spawn(|| log_output(&dest_name, stderr));
+    });
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!(
+            "Trunk build of package `{}` with feature(s) `{}` failed ({status}).",
+            config.package,
+            config.features.join(", ")
         );
-        bail!("Trunk build of package `{}` failed.", config.package);
     }
 
     Ok(())
 }
+
+/// Log the output of a build line by line, prefixed with its destination name.
+fn log_output<R: Read>(dest_name: &str, output: R) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        info!("[{dest_name}] {line}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_iframe_src() -> Result<()> {
+        let config = Config {
+            package: "app".to_string(),
+            features: vec![],
+            files: None,
+            show_files: None,
+            file_replacements: None,
+            url_query: Some("page=1".to_string()),
+            url_fragment: None,
+            attributes: None,
+        };
+
+        for (url_base, chapter_path, expected) in [
+            (
+                UrlBase::Absolute("/my-book/".to_string()),
+                "a/b/c.md",
+                "src=\"/my-book/app--/index.html?page=1\" ",
+            ),
+            (
+                UrlBase::Relative,
+                "index.md",
+                "src=\"app--/index.html?page=1\" data-mdbook-trunk-src=\"app--/index.html?page=1\" ",
+            ),
+            (
+                UrlBase::Relative,
+                "a/b/c.md",
+                "src=\"../../app--/index.html?page=1\" data-mdbook-trunk-src=\"app--/index.html?page=1\" ",
+            ),
+        ] {
+            let iframe = iframe(&config, &url_base, Some(Path::new(chapter_path)))?;
+            assert!(iframe.contains(expected), "{iframe}");
+        }
+
+        Ok(())
+    }
+}